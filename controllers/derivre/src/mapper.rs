@@ -39,3 +39,301 @@ pub fn map_ast<'a, T, S>(
 
     unreachable!()
 }
+
+/// Like [`map_ast`], but `map_node` can fail. The explicit work-stack is
+/// simply dropped (via early return) on the first `Err`, so callers doing
+/// semantic checks (undefined rule references, arity mismatches, ...)
+/// during the fold get `?`-style propagation without a second pass.
+pub fn try_map_ast<'a, T, S, E>(
+    ast: &'a T,
+    get_args: impl Fn(&T) -> &[T],
+    mut map_node: impl FnMut(&T, Vec<S>) -> Result<S, E>,
+) -> Result<S, E> {
+    let mut stack = vec![StackNode {
+        ast,
+        trg: 0,
+        args: Vec::new(),
+    }];
+
+    while let Some(entry) = stack.pop() {
+        let args = get_args(entry.ast);
+        if args.len() > 0 && entry.args.len() == 0 {
+            let trg = stack.len();
+            stack.push(entry);
+            for ast in args {
+                stack.push(StackNode {
+                    ast,
+                    trg,
+                    args: Vec::new(),
+                });
+            }
+        } else {
+            assert!(entry.args.len() == args.len());
+            let r = map_node(entry.ast, entry.args)?;
+            if stack.len() == 0 {
+                return Ok(r);
+            }
+            stack[entry.trg].args.push(r);
+        }
+    }
+
+    unreachable!()
+}
+
+struct CtxStackNode<'a, T, S, C> {
+    ast: &'a T,
+    trg: usize,
+    args: Vec<S>,
+    ctx: C,
+}
+
+/// Attribute-grammar-style variant of [`map_ast`] that threads an
+/// inherited context `C` downward as well as folding synthesized results
+/// `S` upward. `derive_ctx(parent, parent_ctx, child_index)` computes a
+/// child's context from its parent's; it is never called for the root,
+/// whose context is the caller-supplied `ctx` seed.
+pub fn map_ast_ctx<'a, T, S, C>(
+    ast: &'a T,
+    ctx: C,
+    get_args: impl Fn(&T) -> &[T],
+    derive_ctx: impl Fn(&T, &C, usize) -> C,
+    mut map_node: impl FnMut(&T, &C, Vec<S>) -> S,
+) -> S {
+    let mut stack = vec![CtxStackNode {
+        ast,
+        trg: 0,
+        args: Vec::new(),
+        ctx,
+    }];
+
+    while let Some(entry) = stack.pop() {
+        let args = get_args(entry.ast);
+        if args.len() > 0 && entry.args.len() == 0 {
+            let trg = stack.len();
+            let child_ctxs: Vec<C> = (0..args.len())
+                .map(|i| derive_ctx(entry.ast, &entry.ctx, i))
+                .collect();
+            stack.push(entry);
+            for (ast, ctx) in args.iter().zip(child_ctxs) {
+                stack.push(CtxStackNode {
+                    ast,
+                    trg,
+                    args: Vec::new(),
+                    ctx,
+                });
+            }
+        } else {
+            assert!(entry.args.len() == args.len());
+            let r = map_node(entry.ast, &entry.ctx, entry.args);
+            if stack.len() == 0 {
+                return r;
+            }
+            stack[entry.trg].args.push(r);
+        }
+    }
+
+    unreachable!()
+}
+
+enum UnparseAction<'a, T> {
+    Visit(&'a T, isize),
+    Emit(String),
+}
+
+/// Iterative AST-to-source pretty-printer built on the same explicit-stack
+/// technique as [`map_ast`], so it doesn't recurse natively and can't
+/// overflow on deep ASTs. For each node, `render(node, indent)` returns
+/// `(open, sep, close, child_indent_delta)`; children are emitted between
+/// `open` and `close`, separated by `sep`, each seeing `indent +
+/// child_indent_delta`. Output is deterministic and round-trippable, which
+/// makes it useful for debugging compiled grammars and for golden-file
+/// tests.
+pub fn unparse_ast<'a, T>(
+    ast: &'a T,
+    get_args: impl Fn(&T) -> &[T],
+    mut render: impl FnMut(&T, isize) -> (String, String, String, isize),
+) -> String {
+    let mut out = String::new();
+    let mut stack = vec![UnparseAction::Visit(ast, 0)];
+
+    while let Some(action) = stack.pop() {
+        match action {
+            UnparseAction::Emit(s) => out.push_str(&s),
+            UnparseAction::Visit(node, indent) => {
+                let (open, sep, close, delta) = render(node, indent);
+                let child_indent = indent + delta;
+                let args = get_args(node);
+
+                stack.push(UnparseAction::Emit(close));
+                for i in (0..args.len()).rev() {
+                    if i + 1 < args.len() {
+                        stack.push(UnparseAction::Emit(sep.clone()));
+                    }
+                    stack.push(UnparseAction::Visit(&args[i], child_indent));
+                }
+                stack.push(UnparseAction::Emit(open));
+            }
+        }
+    }
+
+    out
+}
+
+/// Bottom-up rewrite-to-fixpoint, built on [`map_ast`]: each sweep folds
+/// `ast` into a freshly rebuilt tree via `rewrite(node, rewritten_children)`,
+/// and sweeps repeat until one makes no change (by `T`'s `PartialEq`) or
+/// `max_passes` is reached. `rewrite` must be monotone/terminating for the
+/// rule set it implements; `max_passes` is only a guard against rules that
+/// oscillate instead of converging.
+pub fn rewrite_ast<T: Clone + PartialEq>(
+    ast: &T,
+    get_children: impl Fn(&T) -> &[T],
+    mut rewrite: impl FnMut(T, Vec<T>) -> T,
+    max_passes: usize,
+) -> T {
+    let mut current = ast.clone();
+    for _ in 0..max_passes {
+        let next = map_ast(&current, &get_children, |node, children| {
+            rewrite(node.clone(), children)
+        });
+        if next == current {
+            return next;
+        }
+        current = next;
+    }
+    current
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Expr {
+        Num(i64),
+        Add(Vec<Expr>),
+    }
+
+    fn children(e: &Expr) -> &[Expr] {
+        match e {
+            Expr::Num(_) => &[],
+            Expr::Add(cs) => cs,
+        }
+    }
+
+    fn tree() -> Expr {
+        // (1 + (2 + 3)) + 4
+        Expr::Add(vec![
+            Expr::Add(vec![Expr::Num(1), Expr::Add(vec![Expr::Num(2), Expr::Num(3)])]),
+            Expr::Num(4),
+        ])
+    }
+
+    #[test]
+    fn map_ast_sums_leaves() {
+        let sum = map_ast(&tree(), children, |node, child_sums: Vec<i64>| match node {
+            Expr::Num(n) => *n,
+            Expr::Add(_) => child_sums.iter().sum(),
+        });
+        assert_eq!(sum, 10);
+    }
+
+    #[test]
+    fn try_map_ast_propagates_first_error() {
+        let bad = Expr::Add(vec![Expr::Num(1), Expr::Num(-1)]);
+        let r: Result<i64, &'static str> = try_map_ast(&bad, children, |node, child_sums| match node {
+            Expr::Num(n) if *n < 0 => Err("negative leaf"),
+            Expr::Num(n) => Ok(*n),
+            Expr::Add(_) => Ok(child_sums.iter().sum()),
+        });
+        assert_eq!(r, Err("negative leaf"));
+
+        let ok: Result<i64, &'static str> = try_map_ast(&tree(), children, |node, child_sums| match node {
+            Expr::Num(n) => Ok(*n),
+            Expr::Add(_) => Ok(child_sums.iter().sum()),
+        });
+        assert_eq!(ok, Ok(10));
+    }
+
+    #[test]
+    fn map_ast_ctx_threads_depth_downward() {
+        // ctx = depth from root; every leaf must observe the right depth
+        // regardless of the traversal order map_ast_ctx happens to visit
+        // siblings in, so check this as a multiset rather than pinning an
+        // exact emission order.
+        let mut depths = map_ast_ctx(
+            &tree(),
+            0usize,
+            children,
+            |_parent, parent_depth, _child_idx| parent_depth + 1,
+            |node, depth, child_depths: Vec<Vec<usize>>| match node {
+                Expr::Num(_) => vec![*depth],
+                Expr::Add(_) => child_depths.into_iter().flatten().collect(),
+            },
+        );
+        depths.sort_unstable();
+        assert_eq!(depths, vec![1, 2, 3, 3]);
+    }
+
+    #[test]
+    fn unparse_ast_renders_with_separators() {
+        let out = unparse_ast(&tree(), children, |node, _indent| match node {
+            Expr::Num(n) => (n.to_string(), String::new(), String::new(), 0),
+            Expr::Add(_) => ("(".to_string(), " + ".to_string(), ")".to_string(), 0),
+        });
+        assert_eq!(out, "((1 + (2 + 3)) + 4)");
+    }
+
+    #[test]
+    fn rewrite_ast_constant_folds_to_fixpoint() {
+        let folded = rewrite_ast(
+            &tree(),
+            children,
+            |node, rewritten_children| match node {
+                Expr::Num(n) => Expr::Num(n),
+                Expr::Add(_) => {
+                    if rewritten_children.iter().all(|c| matches!(c, Expr::Num(_))) {
+                        let sum: i64 = rewritten_children
+                            .iter()
+                            .map(|c| match c {
+                                Expr::Num(n) => *n,
+                                Expr::Add(_) => unreachable!(),
+                            })
+                            .sum();
+                        Expr::Num(sum)
+                    } else {
+                        Expr::Add(rewritten_children)
+                    }
+                }
+            },
+            8,
+        );
+        assert_eq!(folded, Expr::Num(10));
+    }
+
+    #[test]
+    fn rewrite_ast_max_passes_zero_is_a_no_op() {
+        // `for _ in 0..0` never runs, so with max_passes == 0 rewrite_ast
+        // must hand back the input unchanged, regardless of what `rewrite`
+        // would have done with a chance to run.
+        let untouched = rewrite_ast(
+            &tree(),
+            children,
+            |node, rewritten_children| match node {
+                Expr::Num(n) => Expr::Num(n),
+                Expr::Add(_) => {
+                    let sum: i64 = rewritten_children
+                        .iter()
+                        .map(|c| match c {
+                            Expr::Num(n) => *n,
+                            Expr::Add(_) => 0,
+                        })
+                        .sum();
+                    Expr::Num(sum)
+                }
+            },
+            0,
+        );
+        assert_eq!(untouched, tree());
+    }
+}
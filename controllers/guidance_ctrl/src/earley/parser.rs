@@ -1,16 +1,27 @@
-use std::{
-    fmt::{Debug, Display},
+// This module only relies on `core`/`alloc`; a crate that wants to run the
+// parser inside a `no_std` Wasm sandbox enables that by putting
+// `#![no_std]` + `extern crate alloc;` in the crate root and leaving the
+// `std` feature off. The default `std` feature keeps everything working
+// unchanged for hosts that do link `std`.
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+use std::{collections::BTreeMap, rc::Rc};
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, collections::BTreeMap, format, rc::Rc, string::String, vec, vec::Vec};
+
+use core::{
+    fmt::{self, Arguments, Debug, Display, Write as _},
     hash::Hash,
     ops::Range,
-    rc::Rc,
-    vec,
 };
 
 use aici_abi::{
     toktree::{Recognizer, SpecialToken, TokTrie},
     TokenId,
 };
-use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
 
 use super::grammar::{CGrammar, CSymIdx, CSymbol, ModelVariable, RuleIdx};
 
@@ -18,19 +29,218 @@ const DEBUG: bool = false;
 const INFO: bool = true;
 const MAX_ROW: usize = 100;
 
+/// Sink for the parser's trace/debug output. The default (`NullTrace`)
+/// discards everything, so embedders that don't care about tracing pay
+/// nothing; a host running the parser inside a restricted/Wasm sandbox can
+/// plug in its own sink instead of linking `std::io` for `println!`.
+pub trait ParserTrace {
+    fn event(&mut self, msg: Arguments);
+}
+
+/// Default [`ParserTrace`] that throws every event away.
+pub struct NullTrace;
+
+impl ParserTrace for NullTrace {
+    fn event(&mut self, _msg: Arguments) {}
+}
+
 macro_rules! debug {
-    ($($arg:tt)*) => {
+    ($scratch:expr, $($arg:tt)*) => {
         if DEBUG {
-            println!($($arg)*);
+            $scratch.trace.event(format_args!($($arg)*));
         }
     }
 }
 
 macro_rules! info {
-    ($($arg:tt)*) => {
+    ($scratch:expr, $($arg:tt)*) => {
         if INFO {
-            println!($($arg)*);
+            $scratch.trace.event(format_args!($($arg)*));
+        }
+    }
+}
+
+/// Why [`Parser::scan`]/[`Parser::apply_tokens`] rejected a byte, with
+/// enough position info that a caller can explain the rejection (eg. when
+/// reporting why a token got masked during constrained decoding) instead of
+/// string-matching an opaque message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParseReject {
+    /// No item in the current Earley row accepts `byte`.
+    ByteRejected { byte: u8, row: usize },
+    /// `row_infos` already recorded a different byte at this position; the
+    /// token boundary doesn't line up with what was already committed.
+    StaticMismatch {
+        expected: u8,
+        found: u8,
+        byte_idx: usize,
+    },
+    /// The scanned byte only completed a hidden (non-capturing) item, so
+    /// there's nothing to report at this position yet.
+    HiddenItem,
+    /// The current row grew past `max` items; the grammar is likely
+    /// right-recursive and should be rewritten to be left-recursive.
+    RowOverflow { items: usize, max: usize },
+    /// [`Parser::scan_utf8`] was fed an invalid or overlong UTF-8 sequence
+    /// starting with this lead byte.
+    InvalidUtf8 { lead: u8 },
+}
+
+impl Display for ParseReject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseReject::ByteRejected { byte, row } => {
+                write!(f, "byte {byte} rejected at row {row}")
+            }
+            ParseReject::StaticMismatch {
+                expected,
+                found,
+                byte_idx,
+            } => write!(
+                f,
+                "byte mismatch at {byte_idx}: expected {expected}, found {found}"
+            ),
+            ParseReject::HiddenItem => write!(f, "scan only completed a hidden item"),
+            ParseReject::RowOverflow { items, max } => write!(
+                f,
+                "current row has {items} items; max is {max}; consider making your grammar left-recursive if it's right-recursive"
+            ),
+            ParseReject::InvalidUtf8 { lead } => {
+                write!(f, "invalid or overlong UTF-8 sequence starting with byte {lead:#04x}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseReject {}
+
+/// Outcome of [`Parser::classify`]: whether the current row is a finished
+/// parse, could still be extended, or is a dead end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseStatus {
+    /// Accepting, and no item in the current row still expects a following
+    /// symbol: there's nothing left that could extend the parse.
+    Complete,
+    /// At least one item in the current row still expects a following
+    /// symbol, so more bytes could extend the parse.
+    Incomplete,
+    /// The current row has no viable items: no continuation is possible.
+    Dead,
+}
+
+/// Outcome of [`Parser::scan_utf8`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Utf8Outcome {
+    /// The byte continued a UTF-8 sequence that isn't complete yet; the
+    /// Earley state wasn't touched.
+    Incomplete,
+    /// A full scalar value was assembled and accepted by the grammar.
+    Accepted(char),
+}
+
+/// Buffers the bytes of a UTF-8 sequence for [`Parser::scan_utf8`], modeled
+/// on the standard UTF-8 slow path: the lead byte determines how many
+/// continuation bytes to expect, and the scalar value is only decoded once
+/// they've all arrived.
+#[derive(Default)]
+struct Utf8Decoder {
+    buf: [u8; 4],
+    len: u8,
+    want: u8,
+}
+
+impl Utf8Decoder {
+    fn seq_len(lead: u8) -> Option<u8> {
+        match lead {
+            0x00..=0x7f => Some(1),
+            0xc2..=0xdf => Some(2),
+            0xe0..=0xef => Some(3),
+            0xf0..=0xf4 => Some(4),
+            // stray continuation byte, overlong C0/C1 lead, or invalid F5-FF
+            _ => None,
+        }
+    }
+
+    /// Buffer one more byte. `Ok(Some(..))` carries the completed sequence
+    /// (and its length) once enough bytes have arrived; `Ok(None)` means
+    /// more are still pending; `Err(())` means `byte` can't start or
+    /// continue a valid sequence.
+    fn push(&mut self, byte: u8) -> Result<Option<([u8; 4], u8)>, ()> {
+        if self.len == 0 {
+            self.want = Self::seq_len(byte).ok_or(())?;
+        } else if byte & 0xc0 != 0x80 {
+            self.len = 0;
+            return Err(());
+        }
+        self.buf[self.len as usize] = byte;
+        self.len += 1;
+        if self.len < self.want {
+            Ok(None)
+        } else {
+            let done = (self.buf, self.len);
+            self.len = 0;
+            self.want = 0;
+            Ok(Some(done))
+        }
+    }
+}
+
+/// The legal-next-byte set returned by [`Parser::allowed_bytes`]: bit `b`
+/// set means scanning byte `b` against the current row would be accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ByteSet([u64; 4]);
+
+impl ByteSet {
+    fn empty() -> Self {
+        ByteSet([0; 4])
+    }
+
+    fn insert(&mut self, b: u8) {
+        self.0[(b >> 6) as usize] |= 1u64 << (b & 63);
+    }
+
+    pub fn contains(&self, b: u8) -> bool {
+        self.0[(b >> 6) as usize] & (1u64 << (b & 63)) != 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0u16..256).map(|b| b as u8).filter(move |&b| self.contains(b))
+    }
+}
+
+/// Structured, serde-serializable explanation of the current parse state,
+/// returned by [`Parser::explain`] so constrained-decoding tooling can
+/// consume it as JSON instead of string-matching a rejection message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParseDiagnostic {
+    /// `num_rows() - 1` at the time `explain` was called.
+    pub row: usize,
+    /// The rejection the last [`Parser::scan`] call reported, if any.
+    pub rejected: Option<ParseReject>,
+    /// Every byte that would currently extend the parse.
+    pub allowed: Vec<u8>,
+    /// [`Parser::item_to_string`]-rendered items still active in the
+    /// current row.
+    pub in_progress: Vec<String>,
+}
+
+impl Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(rejected) = &self.rejected {
+            writeln!(f, "rejected: {rejected}")?;
+        }
+        writeln!(
+            f,
+            "row {}: {} byte(s) allowed: {:?}",
+            self.row,
+            self.allowed.len(),
+            self.allowed
+        )?;
+        for item in &self.in_progress {
+            writeln!(f, "  {item}")?;
         }
+        Ok(())
     }
 }
 
@@ -46,7 +256,7 @@ struct ItemProps {
 }
 
 impl Display for ItemProps {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if self.hidden_start == usize::MAX {
             write!(f, "")
         } else {
@@ -78,6 +288,53 @@ pub struct Stats {
     pub all_items: usize,
 }
 
+/// A restore point produced by [`Parser::mark`] and consumed by
+/// [`Parser::undo_to`]. Opaque on purpose: the set of lengths/counters it
+/// snapshots is an implementation detail of what `undo_to` needs to restore.
+#[derive(Debug, Clone, Copy)]
+pub struct TrailMark {
+    rows: usize,
+    row_infos: usize,
+    captures: usize,
+    token_idx: usize,
+    last_collapse: usize,
+}
+
+/// Interned id for a capture variable name, handed out by [`CaptureNames`].
+/// Cheap to copy around and compare, unlike the `String` it stands in for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureId(u32);
+
+/// Atom table for capture variable names: `push_row`'s capture branch used
+/// to clone `capture_name` every time a captured nonterminal completed;
+/// names are interned here instead, so the hot path only ever pushes a
+/// `CaptureId`.
+#[derive(Default)]
+struct CaptureNames {
+    names: Vec<String>,
+    by_name: BTreeMap<String, CaptureId>,
+}
+
+impl CaptureNames {
+    fn intern(&mut self, name: &str) -> CaptureId {
+        if let Some(id) = self.by_name.get(name) {
+            return *id;
+        }
+        let id = CaptureId(self.names.len() as u32);
+        self.names.push(name.to_string());
+        self.by_name.insert(name.to_string(), id);
+        id
+    }
+
+    fn id(&self, name: &str) -> Option<CaptureId> {
+        self.by_name.get(name).copied()
+    }
+
+    fn name(&self, id: CaptureId) -> &str {
+        &self.names[id.0 as usize]
+    }
+}
+
 struct Row {
     first_item: usize,
     last_item: usize,
@@ -120,6 +377,7 @@ struct Scratch {
     items: Vec<Item>,
     item_props: Vec<ItemProps>,
     definitive: bool,
+    trace: Box<dyn ParserTrace>,
 }
 
 struct RowInfo {
@@ -132,16 +390,19 @@ struct RowInfo {
 pub struct Parser {
     grammar: Rc<CGrammar>,
     scratch: Scratch,
-    captures: Vec<(String, Vec<u8>)>,
+    capture_names: CaptureNames,
+    captures: Vec<(CaptureId, Vec<u8>)>,
     rows: Vec<Row>,
     row_infos: Vec<RowInfo>,
     stats: Stats,
     last_collapse: usize,
     token_idx: usize,
+    utf8: Utf8Decoder,
+    last_reject: Option<ParseReject>,
 }
 
 impl Scratch {
-    fn new(grammar: Rc<CGrammar>) -> Self {
+    fn new(grammar: Rc<CGrammar>, trace: Box<dyn ParserTrace>) -> Self {
         Scratch {
             grammar,
             row_start: 0,
@@ -149,6 +410,7 @@ impl Scratch {
             items: vec![],
             item_props: vec![],
             definitive: true,
+            trace,
         }
     }
 
@@ -207,6 +469,7 @@ impl Scratch {
             self.merge_item_origin(self.row_end, origin_item_idx);
 
             debug!(
+                self,
                 "      addu: {} ({})",
                 self.item_to_string(self.row_end),
                 info
@@ -226,8 +489,9 @@ impl Scratch {
     fn set_hidden_start(&mut self, item: Item, hidden_start: usize) {
         let idx = self.find_item(item).unwrap();
         self.item_props[idx].hidden_start =
-            std::cmp::min(self.item_props[idx].hidden_start, hidden_start);
+            core::cmp::min(self.item_props[idx].hidden_start, hidden_start);
         debug!(
+            self,
             "      hidden: {} {}",
             hidden_start,
             self.item_to_string(idx),
@@ -258,23 +522,33 @@ impl Scratch {
 
 impl Parser {
     pub fn new(grammar: CGrammar) -> Self {
+        Self::new_with_trace(grammar, Box::new(NullTrace))
+    }
+
+    /// Like [`Self::new`], but traces row/event output to `trace` instead of
+    /// discarding it. Useful for embedders that can't (or don't want to)
+    /// link `std::io` for `println!`-based debugging.
+    pub fn new_with_trace(grammar: CGrammar, trace: Box<dyn ParserTrace>) -> Self {
         let start = grammar.start();
         let grammar = Rc::new(grammar);
-        let scratch = Scratch::new(Rc::clone(&grammar));
+        let scratch = Scratch::new(Rc::clone(&grammar), trace);
         let mut r = Parser {
             grammar,
             rows: vec![],
             row_infos: vec![],
+            capture_names: CaptureNames::default(),
             captures: vec![],
             scratch,
             stats: Stats::default(),
             last_collapse: 0,
             token_idx: 0,
+            utf8: Utf8Decoder::default(),
+            last_reject: None,
         };
         for rule in r.grammar.rules_of(start).to_vec() {
             r.scratch.add_unique(Item::new(rule, 0), 0, "init");
         }
-        debug!("initial push");
+        debug!(r.scratch, "initial push");
         let _ = r.push_row(r.scratch.row_start, 0);
         r
     }
@@ -294,10 +568,37 @@ impl Parser {
         false
     }
 
+    /// One-shot readout of whether the current parse state is finished,
+    /// could still accept more bytes, or is a dead end — the three-way split
+    /// a rustyline-style line validator makes, here as `Complete` /
+    /// `Incomplete` / `Dead`, so a REPL or streaming decoder can decide whether to keep
+    /// feeding bytes, stop, or reject without re-deriving it from
+    /// `is_accepting`/`curr_row` itself.
+    pub fn classify(&self) -> ParseStatus {
+        let mut has_items = false;
+        let mut has_continuation = false;
+        for idx in self.curr_row().item_indices() {
+            has_items = true;
+            let item = self.scratch.items[idx];
+            if self.grammar.sym_idx_at(item.rule_idx()) != CSymIdx::NULL {
+                has_continuation = true;
+                break;
+            }
+        }
+        if has_continuation {
+            ParseStatus::Incomplete
+        } else if has_items && self.is_accepting() {
+            ParseStatus::Complete
+        } else {
+            ParseStatus::Dead
+        }
+    }
+
     fn item_to_string(&self, idx: usize) -> String {
         self.scratch.item_to_string(idx)
     }
 
+    #[cfg(feature = "std")]
     pub fn print_row(&self, row_idx: usize) {
         let row = &self.rows[row_idx];
         println!("row {}", row_idx);
@@ -306,6 +607,9 @@ impl Parser {
         }
     }
 
+    #[cfg(not(feature = "std"))]
+    pub fn print_row(&self, _row_idx: usize) {}
+
     pub fn num_rows(&self) -> usize {
         self.rows.len()
     }
@@ -321,6 +625,7 @@ impl Parser {
         // self.rows.drain(self.rows.len() - n..);
     }
 
+    #[cfg(feature = "std")]
     #[allow(dead_code)]
     pub fn print_stats(&mut self) {
         println!("stats: {:?}", self.stats);
@@ -337,6 +642,34 @@ impl Parser {
         self.row_infos.iter().skip(1).map(|ri| ri.byte).collect()
     }
 
+    /// Capture a restore point for [`Self::undo_to`]. Cheaper than cloning
+    /// the whole `Parser`, so beam search / speculative decoding can try a
+    /// candidate byte, inspect the resulting row, and back out again.
+    pub fn mark(&self) -> TrailMark {
+        self.assert_definitive();
+        TrailMark {
+            rows: self.rows.len(),
+            row_infos: self.row_infos.len(),
+            captures: self.captures.len(),
+            token_idx: self.token_idx,
+            last_collapse: self.last_collapse,
+        }
+    }
+
+    /// Roll the parser back to `mark`, undoing every row, byte and capture
+    /// appended since it was taken. `push_row`'s commit-point rewrite and
+    /// `hide_item` both mutate items/`item_props` in place, but only ever
+    /// within the row currently under construction, so truncating back to
+    /// `mark`'s row count also discards those in-place rewrites: there's no
+    /// separate trail of item-level edits to replay.
+    pub fn undo_to(&mut self, mark: TrailMark) {
+        self.assert_definitive();
+        self.pop_row_infos(self.row_infos.len() - mark.row_infos);
+        self.captures.truncate(mark.captures);
+        self.token_idx = mark.token_idx;
+        self.last_collapse = mark.last_collapse;
+    }
+
     fn item_lhs(&self, item: &Item) -> CSymIdx {
         self.grammar.sym_idx_of(item.rule_idx())
     }
@@ -366,12 +699,12 @@ impl Parser {
         trie: &TokTrie,
         tokens: &[TokenId],
         mut num_skip: usize,
-    ) -> Result<&'static str> {
+    ) -> Result<(), ParseReject> {
         // this is unused!
         self.assert_definitive();
         let mut byte_idx = 1; // row_infos[0] has just the 0 byte
         let mut tok_idx = 0;
-        debug!("apply_tokens: {:?}", tokens);
+        debug!(self.scratch, "apply_tokens: {:?}", tokens);
         for t in tokens {
             for b in trie.token(*t).iter() {
                 if num_skip > 0 {
@@ -380,25 +713,28 @@ impl Parser {
                 }
 
                 if byte_idx >= self.row_infos.len() {
-                    if !self.scan(*b) {
-                        return Ok("parse reject");
-                    }
+                    self.scan(*b)?;
                     if byte_idx >= self.row_infos.len() {
-                        return Ok("hidden item");
+                        return Err(ParseReject::HiddenItem);
                     }
                     let item_count = self.curr_row().item_indices().count();
                     if item_count > MAX_ROW {
-                        bail!(
-                            "Current row has {} items; max is {}; consider making your grammar left-recursive if it's right-recursive",
-                            item_count,
-                            MAX_ROW,
-                        );
+                        return Err(ParseReject::RowOverflow {
+                            items: item_count,
+                            max: MAX_ROW,
+                        });
                     }
                 }
                 let info = &mut self.row_infos[byte_idx];
                 if info.byte != *b {
-                    println!("byte mismatch: {} != {} at {}", info.byte, b, byte_idx);
-                    return Ok("static reject");
+                    self.scratch
+                        .trace
+                        .event(format_args!("byte mismatch: {} != {} at {}", info.byte, b, byte_idx));
+                    return Err(ParseReject::StaticMismatch {
+                        expected: info.byte,
+                        found: *b,
+                        byte_idx,
+                    });
                 }
                 info.token_idx = tok_idx;
                 byte_idx += 1;
@@ -410,7 +746,7 @@ impl Parser {
             byte_idx += 1;
         }
         self.token_idx = tok_idx;
-        return Ok("");
+        Ok(())
     }
 
     pub fn filter_max_tokens(&mut self) {
@@ -435,12 +771,13 @@ impl Parser {
                 if max_tokens != usize::MAX {
                     let start_token_idx = self.row_infos[item.start_pos() + 1].token_idx;
                     if self.token_idx - start_token_idx >= max_tokens {
-                        debug!(
-                            "  remove: {}-{} {}",
-                            self.token_idx,
-                            start_token_idx,
-                            self.item_to_string(i)
-                        );
+                        if DEBUG {
+                            let item_str = self.item_to_string(i);
+                            debug!(
+                                self.scratch,
+                                "  remove: {}-{} {}", self.token_idx, start_token_idx, item_str
+                            );
+                        }
                         continue;
                     }
                 }
@@ -456,10 +793,10 @@ impl Parser {
 
     pub fn force_bytes(&mut self) -> Vec<u8> {
         self.assert_definitive();
-        debug!("force_bytes");
+        debug!(self.scratch, "force_bytes");
         let mut bytes = vec![];
         while let Some(b) = self.forced_byte() {
-            if !self.scan(b) {
+            if self.scan(b).is_err() {
                 // shouldn't happen?
                 break;
             }
@@ -519,7 +856,12 @@ impl Parser {
     }
 
     pub fn hide_item(&mut self, sym: CSymIdx, row_idx: usize) -> bool {
-        info!("hide_item: {} {}", self.grammar.sym_data(sym).name, row_idx);
+        info!(
+            self.scratch,
+            "hide_item: {} {}",
+            self.grammar.sym_data(sym).name,
+            row_idx
+        );
 
         let row_range = self.rows[row_idx].item_indices();
         let last_byte = self.row_infos[row_idx].byte;
@@ -535,6 +877,7 @@ impl Parser {
             //info!("  => now: {}", item_to_string(&self.grammar, &item));
             if self.grammar.sym_idx_at(item.rule_idx()) == sym {
                 info!(
+                    self.scratch,
                     "  => add: {}",
                     item_to_string(&self.grammar, &item.advance_dot())
                 );
@@ -553,7 +896,7 @@ impl Parser {
 
     pub fn scan_model_variable(&mut self, mv: ModelVariable) -> bool {
         if self.scratch.definitive {
-            debug!("  scan mv: {:?}", mv);
+            debug!(self.scratch, "  scan mv: {:?}", mv);
         }
 
         self.scratch.new_row(self.curr_row().last_item);
@@ -577,7 +920,7 @@ impl Parser {
     }
 
     #[inline(always)]
-    pub fn scan(&mut self, b: u8) -> bool {
+    pub fn scan(&mut self, b: u8) -> Result<(), ParseReject> {
         let row_idx = self.rows.len() - 1;
         let last = self.rows[row_idx].last_item;
         let mut i = self.rows[row_idx].first_item;
@@ -589,7 +932,7 @@ impl Parser {
         self.scratch.new_row(last);
 
         if self.scratch.definitive {
-            debug!("  scan: {:?}", b as char);
+            debug!(self.scratch, "  scan: {:?}", b as char);
         }
 
         while i < last {
@@ -601,13 +944,155 @@ impl Parser {
             }
             i += 1;
         }
-        self.push_row(self.scratch.row_start, b)
+        if self.push_row(self.scratch.row_start, b) {
+            self.last_reject = None;
+            Ok(())
+        } else {
+            let reject = ParseReject::ByteRejected { byte: b, row: row_idx };
+            self.last_reject = Some(reject);
+            Err(reject)
+        }
+    }
+
+    /// Every byte that would currently extend the parse, computed by
+    /// checking every active item of the current row against the
+    /// grammar's per-byte terminal set. Feeds constrained-decoding logit
+    /// masking and [`Self::explain`].
+    pub fn allowed_bytes(&self) -> ByteSet {
+        let mut set = ByteSet::empty();
+        for byte in 0u16..256 {
+            let byte = byte as u8;
+            let allowed = self.grammar.terminals_by_byte(byte);
+            for idx in self.curr_row().item_indices() {
+                let item = self.scratch.items[idx];
+                let sym = self.grammar.sym_idx_at(item.rule_idx()).as_index();
+                if sym < allowed.len() && allowed[sym] {
+                    set.insert(byte);
+                    break;
+                }
+            }
+        }
+        set
+    }
+
+    /// Structured diagnostics for why the parse stands where it does: the
+    /// rejection the last [`Self::scan`] call reported (if any), the legal
+    /// next-byte set, and every in-progress item in the current row.
+    pub fn explain(&self) -> ParseDiagnostic {
+        ParseDiagnostic {
+            row: self.num_rows() - 1,
+            rejected: self.last_reject,
+            allowed: self.allowed_bytes().iter().collect(),
+            in_progress: self
+                .curr_row()
+                .item_indices()
+                .map(|idx| self.item_to_string(idx))
+                .collect(),
+        }
     }
 
-    pub fn captures(&self) -> &[(String, Vec<u8>)] {
+    /// Render every row's active item set (via [`Self::item_to_string`]) as
+    /// a stable textual snapshot: one block per row, naming the byte that
+    /// produced it. Meant for golden-file regression testing and for
+    /// debugging differential-fuzzing failures, not for production
+    /// diagnostics — use [`Self::explain`] for that.
+    pub fn dump_rows(&self) -> String {
+        let mut out = String::new();
+        for row_idx in 0..self.num_rows() {
+            let byte = self.row_infos.get(row_idx).map(|ri| ri.byte);
+            let _ = writeln!(out, "row {row_idx} (byte {byte:?}):");
+            for idx in self.rows[row_idx].item_indices() {
+                let _ = writeln!(out, "  {}", self.item_to_string(idx));
+            }
+        }
+        out
+    }
+
+    /// Feed `bytes` through [`Self::scan`], stopping at the first rejected
+    /// byte and leaving the parser's state at the last accepted one. Returns
+    /// how many bytes were accepted, so a caller validating a whole
+    /// candidate token/prefix can do it in one call instead of driving
+    /// `scan` itself in a loop.
+    ///
+    /// This build has no vectorized fast path wired up (there's no
+    /// `memchr`-style dependency available in this crate snapshot), so this
+    /// is the same per-byte `scan` the general path already uses; a fast
+    /// path for runs accepted by a single contiguous-byte terminal would
+    /// slot in here without changing the signature.
+    pub fn scan_bytes(&mut self, bytes: &[u8]) -> usize {
+        let mut accepted = 0;
+        for &b in bytes {
+            if self.scan(b).is_err() {
+                break;
+            }
+            accepted += 1;
+        }
+        accepted
+    }
+
+    /// Codepoint-at-a-time counterpart to [`Self::scan`]: buffers `byte` as
+    /// part of a UTF-8 sequence and only advances the Earley state once a
+    /// full scalar value has been assembled. A sequence left mid-way (eg. a
+    /// token boundary lands inside one) is reported as `Incomplete`, not
+    /// rejected; an invalid or overlong sequence is rejected deterministically.
+    ///
+    /// `CGrammar` terminals are still byte sets, not codepoint ranges, in
+    /// this crate snapshot, so a completed scalar is validated by replaying
+    /// its raw bytes through [`Self::scan`] once assembled — the Earley
+    /// state just never observes a row mid-sequence.
+    ///
+    /// Unlike [`Self::scan_bytes`], this call is atomic: a continuation byte
+    /// can still be rejected by the grammar's byte sets partway through the
+    /// replay, so the replay is wrapped in [`Self::mark`]/[`Self::undo_to`]
+    /// and rolled back on error, leaving the parser exactly as it was before
+    /// this call rather than partially advanced into the rejected scalar.
+    pub fn scan_utf8(&mut self, byte: u8) -> Result<Utf8Outcome, ParseReject> {
+        let lead = if self.utf8.len > 0 { self.utf8.buf[0] } else { byte };
+        let (buf, len) = match self.utf8.push(byte) {
+            Ok(Some(done)) => done,
+            Ok(None) => return Ok(Utf8Outcome::Incomplete),
+            Err(()) => return Err(ParseReject::InvalidUtf8 { lead }),
+        };
+        let bytes = &buf[..len as usize];
+        let ch = core::str::from_utf8(bytes)
+            .ok()
+            .and_then(|s| s.chars().next())
+            .ok_or(ParseReject::InvalidUtf8 { lead: bytes[0] })?;
+        let mark = self.mark();
+        for &b in bytes {
+            if let Err(e) = self.scan(b) {
+                self.undo_to(mark);
+                return Err(e);
+            }
+        }
+        Ok(Utf8Outcome::Accepted(ch))
+    }
+
+    pub fn captures(&self) -> &[(CaptureId, Vec<u8>)] {
         &self.captures
     }
 
+    /// Look up the id a capture variable was interned under. Names are
+    /// interned the first time a capture of them completes, so this returns
+    /// `None` for a name that has not been captured yet.
+    pub fn capture_id(&self, name: &str) -> Option<CaptureId> {
+        self.capture_names.id(name)
+    }
+
+    /// Bytes of the most recent completed capture for `id`, if any.
+    pub fn capture_bytes(&self, id: CaptureId) -> Option<&[u8]> {
+        self.captures
+            .iter()
+            .rev()
+            .find(|(cid, _)| *cid == id)
+            .map(|(_, bytes)| bytes.as_slice())
+    }
+
+    /// The variable name `id` was interned from.
+    pub fn capture_name(&self, id: CaptureId) -> &str {
+        self.capture_names.name(id)
+    }
+
     #[inline(always)]
     fn push_row(&mut self, mut agenda_ptr: usize, byte: u8) -> bool {
         let curr_idx = self.rows.len();
@@ -619,8 +1104,9 @@ impl Parser {
             let mut item_idx = agenda_ptr;
             let mut item = self.scratch.items[agenda_ptr];
             agenda_ptr += 1;
-            if self.scratch.definitive {
-                debug!("    agenda: {}", self.item_to_string(item_idx));
+            if self.scratch.definitive && DEBUG {
+                let item_str = self.item_to_string(item_idx);
+                debug!(self.scratch, "    agenda: {}", item_str);
             }
 
             let rule = item.rule_idx();
@@ -651,11 +1137,13 @@ impl Parser {
                         bytes.drain(hidden_start - item.start_pos()..);
                     }
                     debug!(
+                        self.scratch,
                         "      capture: {} {:?}",
                         var_name,
                         String::from_utf8_lossy(&bytes)
                     );
-                    self.captures.push((var_name.clone(), bytes));
+                    let id = self.capture_names.intern(var_name);
+                    self.captures.push((id, bytes));
                 }
 
                 if item.start_pos() < curr_idx {
@@ -692,7 +1180,10 @@ impl Parser {
                     item_idx = agenda_ptr - 1;
                     commit_item = item;
                     if self.scratch.definitive {
-                        debug!("  commit point: {}", self.item_to_string(item_idx));
+                        if DEBUG {
+                            let item_str = self.item_to_string(item_idx);
+                            debug!(self.scratch, "  commit point: {}", item_str);
+                        }
                         if flags.hidden() {
                             return self.hide_item(lhs, item.start_pos());
                         }
@@ -757,6 +1248,7 @@ impl Recognizer for Parser {
     }
 
     fn special_allowed(&mut self, tok: SpecialToken) -> bool {
+        #[cfg(feature = "std")]
         if false {
             self.print_row(self.num_rows() - 1);
             println!(
@@ -795,7 +1287,7 @@ impl Recognizer for Parser {
     }
 
     fn try_push_byte(&mut self, byte: u8) -> bool {
-        self.scan(byte)
+        self.scan(byte).is_ok()
     }
 }
 
@@ -806,3 +1298,57 @@ fn item_to_string(g: &CGrammar, item: &Item) -> String {
         item.start_pos(),
     )
 }
+
+/// Differential-testing helper for the Earley engine; it compares
+/// accept/reject results only and does not use [`Parser::dump_rows`].
+/// Exercises the scan/commit/rollback machinery,
+/// which otherwise has no coverage in this crate.
+///
+/// A `dir_tests`-style corpus runner that reads grammar+input fixtures from
+/// a directory and diffs [`Parser::dump_rows`] against checked-in golden
+/// files, regenerating them on demand, would build on
+/// [`assert_definitive_matches_speculative`] — but it needs a way to
+/// construct a `CGrammar` from a fixture file, and that constructor isn't
+/// part of this crate snapshot (`super::grammar` only exposes the compiled
+/// `CGrammar` type here, not a builder). Left as a follow-up once that's
+/// available; what's below is runnable today against any two `Parser`s over
+/// the same grammar.
+#[cfg(feature = "std")]
+pub mod fuzz {
+    use super::{Parser, Recognizer};
+
+    /// Feed `bytes` through `definitive` via [`Parser::scan`] and through
+    /// `speculative` via the `Recognizer`/`try_push_byte` path (the one the
+    /// token trie walk uses), asserting both accept/reject identically at
+    /// every prefix and that `row_infos.len() <= num_rows()` holds after
+    /// each step — the invariant `Recognizer::trie_finished` asserts
+    /// before cleaning up the speculative rows. `definitive` and `speculative` must be freshly constructed
+    /// over the same grammar.
+    ///
+    /// # Panics
+    /// If the two modes disagree on whether any prefix of `bytes` is
+    /// accepted, or if the `row_infos`/`num_rows` invariant is violated.
+    pub fn assert_definitive_matches_speculative(
+        mut definitive: Parser,
+        mut speculative: Parser,
+        bytes: &[u8],
+    ) {
+        speculative.trie_started();
+        for (i, &b) in bytes.iter().enumerate() {
+            let accepted_definitive = definitive.scan(b).is_ok();
+            let accepted_speculative = speculative.try_push_byte(b);
+            assert_eq!(
+                accepted_definitive, accepted_speculative,
+                "definitive/speculative mismatch at byte {i} ({b:#04x})"
+            );
+            assert!(
+                speculative.row_infos.len() <= speculative.num_rows(),
+                "row_infos.len() > num_rows() after byte {i}"
+            );
+            if !accepted_definitive {
+                break;
+            }
+        }
+        speculative.trie_finished();
+    }
+}
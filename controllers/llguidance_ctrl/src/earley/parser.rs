@@ -28,6 +28,11 @@ const DEBUG: bool = true;
 
 const MAX_ROW: usize = 100;
 
+/// Default bound on how many chained single-byte lexemes `advance_parser`
+/// will fold in one go before giving up cleanly (see `max_lexeme_chain_depth`
+/// on [`Parser`]).
+const DEFAULT_MAX_LEXEME_CHAIN_DEPTH: usize = 32;
+
 macro_rules! trace {
     ($($arg:tt)*) => {
         if cfg!(feature = "logging") && TRACE {
@@ -101,6 +106,34 @@ impl ParserStats {
     }
 }
 
+/// Why the most recent definitive `try_push_byte_definitive` call failed,
+/// for tooling that wants to explain a constrained-decoding mismatch
+/// instead of just seeing the byte get masked. See [`Parser::last_rejection`].
+#[derive(Debug, Clone)]
+pub struct ParserRejection {
+    /// The byte that was rejected (`None` for a rejected forced end-of-input).
+    pub byte: Option<u8>,
+    /// Lexemes the lexer would have accepted from the lexer state the byte
+    /// was rejected in, rendered via the lexer spec's debug formatting.
+    pub allowed_lexemes: String,
+    /// The Earley items active on the row at rejection time, one per entry.
+    pub active_items: Vec<String>,
+}
+
+impl Display for ParserRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.byte {
+            Some(b) => write!(f, "byte {:#04x} ({:?}) rejected", b, b as char)?,
+            None => write!(f, "end-of-input rejected")?,
+        }
+        write!(f, "; expected one of {}", self.allowed_lexemes)?;
+        for item in &self.active_items {
+            write!(f, "\n  {}", item)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone)]
 struct Row {
     first_item: usize,
@@ -144,8 +177,13 @@ struct Scratch {
     grammar: Arc<CGrammar>,
     row_start: usize,
     row_end: usize,
-    items: Vec<Item>,
-    item_props: Vec<ItemProps>,
+    // Arc'd (rather than plain Vec) so that forking a Parser mid-chart is an
+    // O(1) refcount bump instead of an O(chart size) deep copy; see
+    // `Parser::fork`. Mutated through `Arc::make_mut`, which only pays the
+    // real copy cost the first time a forked branch writes to a chart it's
+    // still sharing with a sibling.
+    items: Arc<Vec<Item>>,
+    item_props: Arc<Vec<ItemProps>>,
     definitive: bool,
 }
 
@@ -191,16 +229,35 @@ struct LexerState {
     byte: Option<u8>,
 }
 
+/// Opaque snapshot of [`Parser`] state produced by [`Parser::checkpoint`]
+/// and consumed by [`Parser::rollback`].
+#[derive(Clone, Copy)]
+pub struct ParserCheckpoint {
+    lexer_stack: usize,
+    rows: usize,
+    row_infos: usize,
+    captures: usize,
+    last_collapse: usize,
+    token_idx: usize,
+    byte_idx: usize,
+    row_start: usize,
+    row_end: usize,
+}
+
 #[derive(Clone)]
 pub struct Parser {
     lexer: Lexer,
     grammar: Arc<CGrammar>,
     scratch: Scratch,
     trie_lexer_stack: usize,
-    captures: Vec<(String, Vec<u8>)>,
-    lexer_stack: Vec<LexerState>,
-    rows: Vec<Row>,
-    row_infos: Vec<RowInfo>,
+    // Arc'd for the same reason as `Scratch::items`/`item_props`: these are
+    // the rest of the per-branch chart state, and sharing them via
+    // refcounted pointers is what makes `fork()` O(1). Mutate only through
+    // `Arc::make_mut`.
+    captures: Arc<Vec<(String, Vec<u8>)>>,
+    lexer_stack: Arc<Vec<LexerState>>,
+    rows: Arc<Vec<Row>>,
+    row_infos: Arc<Vec<RowInfo>>,
     pub(crate) stats: ParserStats,
     last_collapse: usize,
     token_idx: usize,
@@ -208,6 +265,8 @@ pub struct Parser {
     options: GenGrammarOptions,
     trie_gen_grammar: Option<CSymIdx>,
     trie_gen_grammar_accepting: bool,
+    max_lexeme_chain_depth: usize,
+    last_rejection: Option<ParserRejection>,
 }
 
 impl Scratch {
@@ -216,8 +275,8 @@ impl Scratch {
             grammar,
             row_start: 0,
             row_end: 0,
-            items: vec![],
-            item_props: vec![],
+            items: Arc::new(vec![]),
+            item_props: Arc::new(vec![]),
             definitive: true,
         }
     }
@@ -243,15 +302,16 @@ impl Scratch {
     fn ensure_items(&mut self, n: usize) {
         if self.items.len() < n {
             let missing = n - self.items.len();
-            self.items.reserve(missing);
-            unsafe { self.items.set_len(n) }
+            let items = Arc::make_mut(&mut self.items);
+            items.reserve(missing);
+            unsafe { items.set_len(n) }
         }
     }
 
     #[inline(always)]
     fn merge_item_origin(&mut self, target_item_idx: usize, origin_item_idx: usize) {
         let origin = self.item_props[origin_item_idx].clone();
-        self.item_props[target_item_idx].merge(origin);
+        Arc::make_mut(&mut self.item_props)[target_item_idx].merge(origin);
     }
 
     #[inline(always)]
@@ -259,14 +319,17 @@ impl Scratch {
         self.ensure_items(self.row_end + 1);
         // SAFETY: we just ensured that there is enough space
         unsafe {
-            self.items.as_mut_ptr().add(self.row_end).write(item);
+            Arc::make_mut(&mut self.items)
+                .as_mut_ptr()
+                .add(self.row_end)
+                .write(item);
         }
         // self.items[self.row_end] = item;
         if self.definitive {
             if self.item_props.len() <= self.row_end {
-                self.item_props.push(ItemProps::default());
+                Arc::make_mut(&mut self.item_props).push(ItemProps::default());
             } else {
-                self.item_props[self.row_end] = ItemProps::default();
+                Arc::make_mut(&mut self.item_props)[self.row_end] = ItemProps::default();
             }
             self.merge_item_origin(self.row_end, origin_item_idx);
 
@@ -289,8 +352,8 @@ impl Scratch {
 
     fn set_hidden_start(&mut self, item: Item, hidden_start: usize) {
         let idx = self.find_item(item).unwrap();
-        self.item_props[idx].hidden_start =
-            std::cmp::min(self.item_props[idx].hidden_start, hidden_start);
+        let new_start = std::cmp::min(self.item_props[idx].hidden_start, hidden_start);
+        Arc::make_mut(&mut self.item_props)[idx].hidden_start = new_start;
         debug!(
             "      hidden: {} {}",
             hidden_start,
@@ -336,9 +399,9 @@ impl Parser {
             grammar,
             lexer,
             trie_lexer_stack: usize::MAX,
-            rows: vec![],
-            row_infos: vec![],
-            captures: vec![],
+            rows: Arc::new(vec![]),
+            row_infos: Arc::new(vec![]),
+            captures: Arc::new(vec![]),
             scratch,
             stats: ParserStats::default(),
             last_collapse: 0,
@@ -347,11 +410,13 @@ impl Parser {
             options,
             trie_gen_grammar: None,
             trie_gen_grammar_accepting: false,
-            lexer_stack: vec![LexerState {
+            max_lexeme_chain_depth: DEFAULT_MAX_LEXEME_CHAIN_DEPTH,
+            last_rejection: None,
+            lexer_stack: Arc::new(vec![LexerState {
                 row_idx: 0,
                 lexer_state,
                 byte: None,
-            }],
+            }]),
         };
         for rule in r.grammar.rules_of(start).to_vec() {
             r.scratch.add_unique(Item::new(rule, 0), 0, "init");
@@ -365,15 +430,52 @@ impl Parser {
         assert!(r.lexer_stack.len() == 1);
         // set the correct initial lexer state
         // the initial state, shall not allow the SKIP lexeme
-        r.rows[0]
+        Arc::make_mut(&mut r.rows)[0]
             .allowed_lexemes
             .set(LexemeIdx::SKIP.as_usize(), false);
-        r.lexer_stack[0].lexer_state = r.lexer.start_state(&r.rows[0].allowed_lexemes, None);
+        let initial_state = r.lexer.start_state(&r.rows[0].allowed_lexemes, None);
+        Arc::make_mut(&mut r.lexer_stack)[0].lexer_state = initial_state;
         r.assert_definitive();
 
         Ok(r)
     }
 
+    /// Fork the parser so the returned copy can be advanced independently
+    /// (e.g. as a separate beam-search hypothesis) without disturbing
+    /// `self`.
+    ///
+    /// This is an O(1) pointer copy, not an O(state size) deep clone:
+    /// `lexer_stack`/`rows`/`row_infos`/`captures` (and, inside `Scratch`,
+    /// `items`/`item_props`) are each held behind an `Arc`, so `fork()` just
+    /// bumps a handful of refcounts, the same shared-tail-stack trick
+    /// persistent parsers (e.g. lrpar's `Cactus`) use, implemented here via
+    /// `Arc` + copy-on-write rather than a dedicated linked-node type, so
+    /// every existing index/`push`/`truncate`/`iter` call site on these
+    /// fields keeps working unchanged. Every mutation of one of these
+    /// fields goes through `Arc::make_mut`, which clones the underlying
+    /// `Vec` the first time a forked branch writes to storage it's still
+    /// sharing with a sibling (or with `self`), and is free on every write
+    /// after that. So two forks that both keep advancing still end up
+    /// paying for two copies overall — same as a deep clone — but a fork
+    /// that's abandoned without ever being written to (the common case for
+    /// e.g. a beam-search hypothesis that gets pruned before its first
+    /// token) costs nothing beyond the refcount bump.
+    ///
+    /// Because the first write after a fork still pays a real O(state)
+    /// copy, do not call `fork()` on every edge of a search (e.g. once per
+    /// candidate byte in a loop) and then mutate every branch — that still
+    /// costs what a deep clone would. For backtracking search over a
+    /// single hypothesis, use [`Self::checkpoint`]/[`Self::rollback`]
+    /// instead, which only save/restore vector lengths and a few scalars
+    /// and never clone chart storage at all. `fork()` is for the cases
+    /// where two hypotheses must really stay alive and advance
+    /// independently at the same time, e.g. a handful of beam-search
+    /// branches, not an inner search loop; see `repair::repair_parse` for a
+    /// search rewritten onto checkpoint/rollback for exactly that reason.
+    pub fn fork(&self) -> Parser {
+        self.clone()
+    }
+
     pub fn compute_bias_after_gen_grammar(
         &mut self,
         trie: &TokTrie,
@@ -505,7 +607,64 @@ impl Parser {
 
     fn pop_lexer_states(&mut self, n: usize) {
         assert!(self.lexer_stack.len() > n);
-        unsafe { self.lexer_stack.set_len(self.lexer_stack.len() - n) }
+        let new_len = self.lexer_stack.len() - n;
+        unsafe { Arc::make_mut(&mut self.lexer_stack).set_len(new_len) }
+    }
+
+    /// Snapshot the parser state so it can later be restored with
+    /// [`Self::rollback`]. Lets a controller push a whole speculative
+    /// token (several bytes through [`Recognizer::try_push_byte`]),
+    /// inspect acceptance, and cheaply revert if the draft is rejected,
+    /// without re-feeding the prefix.
+    pub fn checkpoint(&self) -> ParserCheckpoint {
+        ParserCheckpoint {
+            lexer_stack: self.lexer_stack.len(),
+            rows: self.rows.len(),
+            row_infos: self.row_infos.len(),
+            captures: self.captures.len(),
+            last_collapse: self.last_collapse,
+            token_idx: self.token_idx,
+            byte_idx: self.byte_idx,
+            row_start: self.scratch.row_start,
+            row_end: self.scratch.row_end,
+        }
+    }
+
+    /// Restore state captured by [`Self::checkpoint`], truncating
+    /// `lexer_stack`/`rows`/`row_infos`/`captures` back to the saved
+    /// lengths (O(states popped)). Rejects rolling back past a
+    /// [`Recognizer::collapse`] boundary, since `collapse()` means
+    /// "committed, can no longer backtrack past this point".
+    ///
+    /// This collapse-boundary rejection has no accompanying unit test:
+    /// doing so needs a real `Parser`, and `Parser::new` requires an
+    /// `Arc<CGrammar>` plus a `Lexer` built from its spec — neither type has
+    /// a constructor (or even a source file) in this crate checkout, so
+    /// there's no way to build a test fixture here without inventing an
+    /// unseen grammar-builder API. Cover it once a `CGrammar`/`Lexer`
+    /// construction path lands in this checkout.
+    pub fn rollback(&mut self, cp: ParserCheckpoint) -> Result<()> {
+        ensure!(
+            cp.row_infos >= self.last_collapse,
+            "cannot roll back a Parser past a collapse() boundary"
+        );
+        self.pop_lexer_states(self.lexer_stack.len() - cp.lexer_stack);
+        Arc::make_mut(&mut self.rows).truncate(cp.rows);
+        Arc::make_mut(&mut self.row_infos).truncate(cp.row_infos);
+        Arc::make_mut(&mut self.captures).truncate(cp.captures);
+        self.last_collapse = cp.last_collapse;
+        self.token_idx = cp.token_idx;
+        self.byte_idx = cp.byte_idx;
+        self.scratch.row_start = cp.row_start;
+        self.scratch.row_end = cp.row_end;
+        Ok(())
+    }
+
+    /// Set how many chained single-byte lexemes `advance_parser` will fold
+    /// in one go (see [`Self::max_lexeme_chain_depth`]) before returning a
+    /// clean rejection instead of continuing the chain.
+    pub fn set_max_lexeme_chain_depth(&mut self, depth: usize) {
+        self.max_lexeme_chain_depth = depth;
     }
 
     #[allow(dead_code)]
@@ -538,7 +697,7 @@ impl Parser {
                 bytes = self.curr_row_bytes();
                 trace!("    bytes: {:?}", String::from_utf8_lossy(&bytes));
             };
-            self.row_infos[idx].start_byte_idx = allbytes.len();
+            Arc::make_mut(&mut self.row_infos)[idx].start_byte_idx = allbytes.len();
             indices.extend((0..bytes.len()).map(|_| idx));
             allbytes.extend_from_slice(&bytes);
         }
@@ -591,7 +750,7 @@ impl Parser {
         debug!("apply_tokens: {:?}\n  {}", tokens, trie.tokens_dbg(tokens));
         self.assert_definitive();
         // reset token_idx
-        for ri in self.row_infos.iter_mut() {
+        for ri in Arc::make_mut(&mut self.row_infos).iter_mut() {
             ri.token_idx_start = usize::MAX;
             ri.token_idx_stop = 0;
         }
@@ -611,7 +770,7 @@ impl Parser {
                     self.token_idx = tok_idx; // save local pointer, in case push_row() uses it
                     self.byte_idx = byte_idx;
                     let row_idx = self.num_rows() - 1;
-                    self.row_infos[row_idx].apply_token_idx(tok_idx);
+                    Arc::make_mut(&mut self.row_infos)[row_idx].apply_token_idx(tok_idx);
                     debug!(
                         "  before push: {}",
                         self.row_infos.last().unwrap().dbg(self.lexer_spec())
@@ -660,7 +819,10 @@ impl Parser {
                                     return Ok("parse reject on max_tokens");
                                 }
                             } else {
-                                self.lexer_stack.last_mut().unwrap().lexer_state = new_state;
+                                Arc::make_mut(&mut self.lexer_stack)
+                                    .last_mut()
+                                    .unwrap()
+                                    .lexer_state = new_state;
                             }
                         }
                     }
@@ -676,7 +838,7 @@ impl Parser {
                     last_lexeme = self.num_rows() - 1;
                 } else {
                     loop {
-                        self.row_infos[last_lexeme].apply_token_idx(tok_idx);
+                        Arc::make_mut(&mut self.row_infos)[last_lexeme].apply_token_idx(tok_idx);
                         if last_lexeme >= indices[byte_idx] {
                             break;
                         }
@@ -698,7 +860,7 @@ impl Parser {
 
         self.token_idx = tokens.len();
         while last_lexeme < self.row_infos.len() {
-            self.row_infos[last_lexeme].apply_token_idx(self.token_idx);
+            Arc::make_mut(&mut self.row_infos)[last_lexeme].apply_token_idx(self.token_idx);
             last_lexeme += 1;
         }
 
@@ -716,7 +878,7 @@ impl Parser {
 
         let mut dst = 0;
 
-        self.row_infos.push(RowInfo {
+        Arc::make_mut(&mut self.row_infos).push(RowInfo {
             lexeme: Lexeme::bogus(),
             start_byte_idx: 0,
             token_idx_start: self.token_idx,
@@ -726,10 +888,12 @@ impl Parser {
 
         for idx in 0..self.num_rows() {
             let range = self.rows[idx].item_indices();
-            self.rows[idx].first_item = dst;
+            Arc::make_mut(&mut self.rows)[idx].first_item = dst;
             for i in range {
                 let item = self.scratch.items[i];
-                let item_props = &self.scratch.item_props[i];
+                // clone eagerly into an owned local so this doesn't alias the
+                // `Arc::make_mut` write to the same field a few lines down
+                let item_props = self.scratch.item_props[i].clone();
                 let sym_data = self.item_sym_data(&item);
                 let max_tokens = sym_data.props.max_tokens;
                 if max_tokens != usize::MAX {
@@ -744,14 +908,14 @@ impl Parser {
                         continue;
                     }
                 }
-                self.scratch.items[dst] = item;
-                self.scratch.item_props[dst] = item_props.clone();
+                Arc::make_mut(&mut self.scratch.items)[dst] = item;
+                Arc::make_mut(&mut self.scratch.item_props)[dst] = item_props;
                 dst += 1;
             }
-            self.rows[idx].last_item = dst;
+            Arc::make_mut(&mut self.rows)[idx].last_item = dst;
         }
 
-        self.row_infos.pop();
+        Arc::make_mut(&mut self.row_infos).pop();
     }
 
     pub fn force_bytes(&mut self) -> Vec<u8> {
@@ -775,12 +939,77 @@ impl Parser {
         bytes
     }
 
+    /// Like [`Self::force_bytes`], but discovers the whole forced span in
+    /// one speculative pass (instead of re-entering [`Self::run_speculative`]
+    /// once per byte) before replaying it through the definitive path, and
+    /// stops early at `max_len`. Lets callers (e.g. the serving runtime)
+    /// skip per-token model evaluations over deterministic grammar spans
+    /// (closing brackets, fixed keywords, punctuation) and emit them
+    /// directly.
+    ///
+    /// Stops once the current row is accepting, the grammar branches (zero
+    /// or more than one viable next byte), or `max_len` bytes have been
+    /// produced. The probe reuses `try_push_byte`/`pop_bytes`, the same
+    /// lexeme/hidden-byte-aware machinery as [`Self::forced_byte`], and
+    /// `run_speculative` guarantees it never touches the definitive stack;
+    /// only bytes that are actually returned get committed via
+    /// `try_push_byte_definitive`.
+    pub fn forced_bytes(&mut self, max_len: usize) -> Vec<u8> {
+        self.assert_definitive();
+
+        let forced = self.run_speculative(|s| {
+            let mut out = vec![];
+            while out.len() < max_len {
+                // check acceptance as `is_accepting` does, then drop whatever
+                // the flush pushed, so the byte probe below starts from the
+                // unflushed state `try_push_byte_definitive` will see
+                let depth = s.lexer_stack.len();
+                let accepting = s.flush_lexer() && s.row_is_accepting();
+                s.pop_lexer_states(s.lexer_stack.len() - depth);
+                if accepting {
+                    break;
+                }
+                let mut found = None;
+                let mut ambiguous = false;
+                for b in 0..=255u8 {
+                    if s.try_push_byte(b) {
+                        s.pop_bytes(1);
+                        if found.is_some() {
+                            ambiguous = true;
+                            break;
+                        }
+                        found = Some(b);
+                    }
+                }
+                match found {
+                    Some(b) if !ambiguous => {
+                        s.try_push_byte(b);
+                        out.push(b);
+                    }
+                    _ => break,
+                }
+            }
+            out
+        });
+
+        let mut bytes = vec![];
+        for b in forced {
+            if !self.try_push_byte_definitive(Some(b)) {
+                // shouldn't happen: same lexer/grammar state as the speculative probe
+                debug!("  forced_bytes commit reject {}", b as char);
+                break;
+            }
+            bytes.push(b);
+        }
+        bytes
+    }
+
     #[inline(always)]
     fn advance_lexer_or_parser(&mut self, lex_result: LexerResult, curr: LexerState) -> bool {
         match lex_result {
             LexerResult::State(next_state, byte) => {
                 // lexer advanced, but no lexeme - fast path
-                self.lexer_stack.push(LexerState {
+                Arc::make_mut(&mut self.lexer_stack).push(LexerState {
                     row_idx: curr.row_idx,
                     lexer_state: next_state,
                     byte: Some(byte),
@@ -822,6 +1051,7 @@ impl Parser {
 
     pub fn try_push_byte_definitive(&mut self, byte: Option<u8>) -> bool {
         assert!(self.scratch.definitive);
+        self.last_rejection = None;
 
         let curr = self.lexer_state();
         let row = &self.rows[curr.row_idx as usize];
@@ -848,7 +1078,36 @@ impl Parser {
             );
         }
 
-        self.advance_lexer_or_parser(res, curr)
+        let ok = self.advance_lexer_or_parser(res, curr);
+        if !ok {
+            self.record_rejection(byte, curr.row_idx as usize, curr.lexer_state);
+        }
+        ok
+    }
+
+    /// The structured diagnostics for why the most recent
+    /// [`Self::try_push_byte_definitive`] call was rejected, if it was.
+    /// Cleared (set to `None`) at the start of every call to that method,
+    /// so it only ever reflects the most recent attempt.
+    pub fn last_rejection(&self) -> Option<&ParserRejection> {
+        self.last_rejection.as_ref()
+    }
+
+    fn record_rejection(&mut self, byte: Option<u8>, row_idx: usize, lexer_state: StateID) {
+        let (allowed_lexemes, indices) = {
+            let row = &self.rows[row_idx];
+            (
+                self.lexer_spec()
+                    .dbg_lexeme_set(&self.lexer.possible_lexemes(lexer_state)),
+                row.item_indices(),
+            )
+        };
+        let active_items = indices.map(|i| self.item_to_string(i)).collect();
+        self.last_rejection = Some(ParserRejection {
+            byte,
+            allowed_lexemes,
+            active_items,
+        });
     }
 
     fn curr_row(&self) -> &Row {
@@ -983,7 +1242,7 @@ impl Parser {
         if r {
             debug!("  gen_grammar OK");
             let lexer_state = self.lexer_state_for_added_row(lexeme, None);
-            self.lexer_stack.push(lexer_state);
+            Arc::make_mut(&mut self.lexer_stack).push(lexer_state);
             true
         } else {
             debug!("  gen_grammar failed!");
@@ -1062,10 +1321,10 @@ impl Parser {
         let added_row_idx = self.num_rows();
         // the allowed_lexemes were not computed correctly due to us messing
         // with agenda pointer above
-        self.rows[added_row_idx].allowed_lexemes = allowed_lexemes;
+        Arc::make_mut(&mut self.rows)[added_row_idx].allowed_lexemes = allowed_lexemes;
         if self.scratch.definitive {
-            self.row_infos[added_row_idx].max_tokens =
-                self.row_infos[added_row_idx - 1].max_tokens.clone();
+            let prev_max_tokens = self.row_infos[added_row_idx - 1].max_tokens.clone();
+            Arc::make_mut(&mut self.row_infos)[added_row_idx].max_tokens = prev_max_tokens;
         }
         true
     }
@@ -1101,7 +1360,7 @@ impl Parser {
     }
 
     pub fn captures(&self) -> &[(String, Vec<u8>)] {
-        &self.captures
+        self.captures.as_slice()
     }
 
     // lexeme only used for captures (in definitive mode)
@@ -1134,7 +1393,7 @@ impl Parser {
                         .as_ref()
                         .unwrap();
                     let bytes = lexeme.hidden_bytes();
-                    self.captures.push((var_name.clone(), bytes.to_vec()));
+                    Arc::make_mut(&mut self.captures).push((var_name.clone(), bytes.to_vec()));
                 }
 
                 if self.scratch.definitive && flags.capture() {
@@ -1160,7 +1419,7 @@ impl Parser {
                         var_name,
                         String::from_utf8_lossy(&bytes)
                     );
-                    self.captures.push((var_name.clone(), bytes));
+                    Arc::make_mut(&mut self.captures).push((var_name.clone(), bytes));
                 }
 
                 if item.start_pos() < curr_idx {
@@ -1187,7 +1446,7 @@ impl Parser {
                         // nullable capture
                         let var_name = sym_data.props.capture_name.as_ref().unwrap();
                         debug!("      capture: {} NULL", var_name);
-                        self.captures.push((var_name.clone(), vec![]));
+                        Arc::make_mut(&mut self.captures).push((var_name.clone(), vec![]));
                     }
                 }
                 for rule in &sym_data.rules {
@@ -1225,14 +1484,14 @@ impl Parser {
             let idx = self.num_rows();
             let row = self.scratch.work_row(allowed_lexemes);
             if self.rows.len() == 0 || self.rows.len() == idx {
-                self.rows.push(row);
+                Arc::make_mut(&mut self.rows).push(row);
             } else {
-                self.rows[idx] = row;
+                Arc::make_mut(&mut self.rows)[idx] = row;
             }
 
             if self.scratch.definitive {
                 if self.row_infos.len() > idx {
-                    self.row_infos.drain(idx..);
+                    Arc::make_mut(&mut self.row_infos).drain(idx..);
                 }
                 let mut max_tokens_map = HashMap::default();
                 for (lx, mx) in max_tokens {
@@ -1253,7 +1512,7 @@ impl Parser {
                 for lx in to_remove {
                     max_tokens_map.remove(&lx);
                 }
-                self.row_infos.push(RowInfo {
+                Arc::make_mut(&mut self.row_infos).push(RowInfo {
                     lexeme: Lexeme::bogus(),
                     token_idx_start: self.token_idx,
                     token_idx_stop: self.token_idx,
@@ -1328,7 +1587,7 @@ impl Parser {
         };
         if self.scratch.definitive {
             // save lexeme at the last row, before we mess with the stack
-            self.row_infos[added_row - 1].lexeme = lexeme;
+            Arc::make_mut(&mut self.row_infos)[added_row - 1].lexeme = lexeme;
             debug!(
                 "lex: re-start {:?} (via {:?}); allowed: {}",
                 no_hidden.lexer_state,
@@ -1385,7 +1644,7 @@ impl Parser {
                         self.lexer_spec().dbg_lexeme(&Lexeme::just_idx(lex.idx))
                     ),
                 }
-                self.lexer_stack.push(LexerState {
+                Arc::make_mut(&mut self.lexer_stack).push(LexerState {
                     lexer_state,
                     byte: Some(*b),
                     ..no_hidden
@@ -1394,14 +1653,14 @@ impl Parser {
         } else {
             if self.scratch.definitive {
                 // set it up for matching after backtrack
-                self.lexer_stack.push(LexerState {
+                Arc::make_mut(&mut self.lexer_stack).push(LexerState {
                     lexer_state: self.lexer.start_state(added_row_lexemes, None),
                     byte: None,
                     ..no_hidden
                 });
             } else {
                 // prevent any further matches in this branch
-                self.lexer_stack.push(LexerState {
+                Arc::make_mut(&mut self.lexer_stack).push(LexerState {
                     lexer_state: self.lexer.a_dead_state(),
                     byte: None,
                     ..no_hidden
@@ -1415,79 +1674,105 @@ impl Parser {
     /// It either initial lexer states for lazy lexers,
     /// or lexer_initial_state+byte for greedy lexers.
     /// lexer_byte is the byte that led to producing the lexeme.
+    ///
+    /// When a lexeme's transition byte is itself the start of another
+    /// lexeme (`check_for_single_byte_lexeme`), that chained lexeme is
+    /// folded in too, and so on - this is an iterative worklist rather
+    /// than a recursive call, bounded by `max_lexeme_chain_depth` rather
+    /// than a hardcoded depth, so a grammar that chains many forced
+    /// single-byte transitions gets a clean `false` instead of a panic.
+    /// Every lexer_stack frame pushed while following the chain is either
+    /// folded into one top frame on success, or popped back to the entry
+    /// length on failure.
     #[inline(always)]
     fn advance_parser(&mut self, pre_lexeme: PreLexeme) -> bool {
-        let transition_byte = if pre_lexeme.byte_next_row {
-            pre_lexeme.byte
-        } else {
-            None
-        };
-        let lexeme_byte = if pre_lexeme.byte_next_row {
-            None
-        } else {
-            pre_lexeme.byte
-        };
-        let lexeme_idx = pre_lexeme.idx;
+        let entry_len = self.lexer_stack.len();
+        let mut current = pre_lexeme;
+        let mut chain_depth = 0usize;
 
-        let lexeme = if self.scratch.definitive {
-            self.mk_lexeme(lexeme_byte, pre_lexeme)
-        } else {
-            Lexeme::just_idx(lexeme_idx)
-        };
-
-        let scan_res = if lexeme.idx == LexemeIdx::SKIP {
-            self.scan_skip_lexeme(&lexeme)
-        } else {
-            self.scan(&lexeme)
-        };
+        loop {
+            let transition_byte = if current.byte_next_row {
+                current.byte
+            } else {
+                None
+            };
+            let lexeme_byte = if current.byte_next_row {
+                None
+            } else {
+                current.byte
+            };
+            let lexeme_idx = current.idx;
 
-        if scan_res {
-            let mut no_hidden = self.lexer_state_for_added_row(lexeme, transition_byte);
+            let lexeme = if self.scratch.definitive {
+                self.mk_lexeme(lexeme_byte, current)
+            } else {
+                Lexeme::just_idx(lexeme_idx)
+            };
 
-            if pre_lexeme.hidden_len > 0 {
-                self.handle_hidden_bytes(no_hidden, lexeme_byte, pre_lexeme);
+            let scan_res = if lexeme.idx == LexemeIdx::SKIP {
+                self.scan_skip_lexeme(&lexeme)
             } else {
-                if pre_lexeme.byte_next_row && no_hidden.lexer_state.is_dead() {
-                    return false;
+                self.scan(&lexeme)
+            };
+
+            if !scan_res {
+                if self.scratch.definitive {
+                    debug!("  scan failed");
                 }
-                if let Some(b) = transition_byte {
-                    if let Some(second_lexeme) = self
-                        .lexer
-                        .check_for_single_byte_lexeme(no_hidden.lexer_state, b)
-                    {
+                self.pop_lexer_states(self.lexer_stack.len() - entry_len);
+                return false;
+            }
+
+            let mut no_hidden = self.lexer_state_for_added_row(lexeme, transition_byte);
+
+            if current.hidden_len > 0 {
+                self.handle_hidden_bytes(no_hidden, lexeme_byte, current);
+                break;
+            }
+
+            if current.byte_next_row && no_hidden.lexer_state.is_dead() {
+                self.pop_lexer_states(self.lexer_stack.len() - entry_len);
+                return false;
+            }
+
+            if let Some(b) = transition_byte {
+                if let Some(next_lexeme) =
+                    self.lexer.check_for_single_byte_lexeme(no_hidden.lexer_state, b)
+                {
+                    chain_depth += 1;
+                    if chain_depth > self.max_lexeme_chain_depth {
                         if self.scratch.definitive {
-                            debug!("single byte lexeme: {:?}", second_lexeme);
-                        }
-                        no_hidden.byte = None;
-                        self.lexer_stack.push(no_hidden);
-
-                        // disallow recursion depth > 2
-                        assert!(pre_lexeme.byte_next_row);
-                        assert!(!second_lexeme.byte_next_row);
-
-                        let r = self.advance_parser(second_lexeme);
-                        if r {
-                            let new_top = self.lexer_stack.pop().unwrap();
-                            *self.lexer_stack.last_mut().unwrap() = new_top;
-                            return true;
-                        } else {
-                            self.lexer_stack.pop();
-                            return false;
+                            debug!("  lexeme chain exceeded max_lexeme_chain_depth ({})", chain_depth);
                         }
+                        self.pop_lexer_states(self.lexer_stack.len() - entry_len);
+                        return false;
                     }
+                    if self.scratch.definitive {
+                        debug!("chained single byte lexeme: {:?}", next_lexeme);
+                    }
+                    no_hidden.byte = None;
+                    Arc::make_mut(&mut self.lexer_stack).push(no_hidden);
+                    current = next_lexeme;
+                    continue;
                 }
-                self.lexer_stack.push(no_hidden);
             }
-            if self.scratch.definitive {
-                self.assert_definitive();
-            }
-            true
-        } else {
-            if self.scratch.definitive {
-                debug!("  scan failed");
-            }
-            false
+
+            Arc::make_mut(&mut self.lexer_stack).push(no_hidden);
+            break;
+        }
+
+        // Fold every frame pushed while following the chain into the single
+        // top frame, mirroring what the old depth-2 recursion did for its
+        // one allowed chain step.
+        while self.lexer_stack.len() > entry_len + 1 {
+            let new_top = Arc::make_mut(&mut self.lexer_stack).pop().unwrap();
+            *Arc::make_mut(&mut self.lexer_stack).last_mut().unwrap() = new_top;
+        }
+
+        if self.scratch.definitive {
+            self.assert_definitive();
         }
+        true
     }
 }
 
@@ -1507,20 +1792,17 @@ impl Recognizer for Parser {
         self.last_collapse = self.num_rows();
     }
 
-    fn special_allowed(&mut self, _tok: SpecialToken) -> bool {
-        // handle EOS logic outside
-        unreachable!("special_allowed")
-
-        // if self
-        //     .model_variables()
-        //     .contains(&ModelVariable::SpecialToken(tok))
-        // {
-        //     true
-        // } else if tok == SpecialToken::EndOfSentence {
-        //     self.is_accepting() || self.lexer_allows_eos()
-        // } else {
-        //     false
-        // }
+    fn special_allowed(&mut self, tok: SpecialToken) -> bool {
+        if self
+            .model_variables()
+            .contains(&ModelVariable::SpecialToken(tok))
+        {
+            true
+        } else if tok == SpecialToken::EndOfSentence {
+            self.is_accepting() || self.lexer_allows_eos()
+        } else {
+            false
+        }
     }
 
     fn trie_started(&mut self) {
@@ -1550,3 +1832,226 @@ fn item_to_string(g: &CGrammar, item: &Item) -> String {
         item.start_pos(),
     )
 }
+
+/// Minimum-cost repair ("lenient parse") for applying a grammar to
+/// already-generated or user-supplied text that may not conform, CPCT+
+/// style: validate with diagnostics, or measure how far off a completion
+/// is, instead of just rejecting at the first bad byte.
+///
+/// The request this implements describes repair at the *lexeme* level;
+/// `Parser`'s only public advance primitive is byte-level
+/// (`try_push_byte_definitive`), and lexeme boundaries aren't exposed, so
+/// the search below operates byte-by-byte instead (`Insert` inserts one
+/// byte, `Delete` skips one input byte). It's a strict subset of the
+/// lexeme-level design - a real lexeme-aware version would need a
+/// `Lexer`/`Row` API this crate snapshot doesn't expose.
+///
+/// The search itself is iterative-deepening (IDA*, with a trivial zero
+/// heuristic, i.e. plain iterative deepening on total repair cost): rather
+/// than keeping a priority queue of forked `Parser`s alive at once (each of
+/// which pays a full O(state size) copy on its first write after
+/// `Parser::fork()`, see its doc comment), it does a single depth-first walk over one shared,
+/// mutable `Parser`, advancing with `try_push_byte_definitive` and
+/// backtracking with `Parser::checkpoint`/`Parser::rollback`. Each round
+/// tries every path whose total repair cost is within the current `bound`;
+/// if none succeeds, `bound` is raised to the cheapest cost that was
+/// pruned and the walk restarts. The first round to succeed is, by
+/// construction, a minimum-cost repair — there's no separate merge-by-state
+/// step (and so no risk of merging on an unsound proxy key), at the cost of
+/// possibly revisiting the same parser state along different paths.
+///
+/// No unit test accompanies `repair_parse` either, for the same reason
+/// given on [`Parser::rollback`]: every entry point takes or builds a real
+/// `Parser`, and this crate checkout has no `CGrammar`/`Lexer` construction
+/// path to build one from.
+pub mod repair {
+    use super::{Parser, ParserCheckpoint};
+
+    /// A single edit applied to the real input to make it parseable.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum RepairOp {
+        /// Feed a byte that isn't part of the real input.
+        Insert(u8),
+        /// Skip a byte of the real input without feeding it to the parser.
+        Delete,
+        /// Consume the next real input byte unchanged.
+        Shift,
+    }
+
+    /// 0 = Shift, 1 = Delete, 2..=257 = Insert(op - 2). Tried in this order
+    /// so the (usually) free `Shift` of real input is attempted before any
+    /// repair.
+    const TOTAL_OPS: usize = 258;
+
+    /// One node of the depth-first search: the state the parser was in
+    /// before any of this node's ops were tried, so every op attempt (and
+    /// every backtrack out of a child) starts from the same place.
+    struct Frame {
+        cp: ParserCheckpoint,
+        pos: usize,
+        cost: usize,
+        shift_streak: usize,
+        /// `repairs.len()` this node was entered with, i.e. the length to
+        /// truncate back to once every op here has been tried.
+        entry_repairs_len: usize,
+        next_op: usize,
+    }
+
+    /// One bounded depth-first pass: explore every path whose total repair
+    /// cost stays `<= bound`. Returns `Ok(true)` on success (`repairs` and
+    /// `parser` are left at the accepting state), `Ok(false)` if the bound
+    /// was exhausted with no match (`next_bound` is updated to the
+    /// cheapest cost pruned, for the caller's next pass), or `Err(())` if
+    /// `max_steps` ran out.
+    #[allow(clippy::too_many_arguments)]
+    fn dfs_round(
+        parser: &mut Parser,
+        input: &[u8],
+        shift_threshold: usize,
+        bound: usize,
+        steps: &mut usize,
+        max_steps: usize,
+        next_bound: &mut usize,
+        repairs: &mut Vec<RepairOp>,
+    ) -> Result<bool, ()> {
+        let mut stack = vec![Frame {
+            cp: parser.checkpoint(),
+            pos: 0,
+            cost: 0,
+            shift_streak: 0,
+            entry_repairs_len: 0,
+            next_op: 0,
+        }];
+
+        while let Some(top_idx) = stack.len().checked_sub(1) {
+            // Every op attempt for this node starts from the node's own
+            // state, regardless of what the previous attempt (if any) did.
+            parser
+                .rollback(stack[top_idx].cp)
+                .expect("repair_parse: bad checkpoint");
+
+            let next_op = stack[top_idx].next_op;
+            if next_op >= TOTAL_OPS {
+                let frame = stack.pop().unwrap();
+                repairs.truncate(frame.entry_repairs_len.saturating_sub(1));
+                continue;
+            }
+            let (pos, cost, shift_streak) = {
+                let f = &stack[top_idx];
+                (f.pos, f.cost, f.shift_streak)
+            };
+            stack[top_idx].next_op += 1;
+
+            let child = match next_op {
+                0 => {
+                    // Shift: consume the next real input byte unchanged, cost 0.
+                    (pos < input.len() && parser.try_push_byte_definitive(Some(input[pos])))
+                        .then(|| (RepairOp::Shift, pos + 1, cost, shift_streak + 1))
+                }
+                1 => {
+                    // Delete: skip the next real input byte, cost 1.
+                    (pos < input.len()).then(|| (RepairOp::Delete, pos + 1, cost + 1, 0))
+                }
+                op => {
+                    // Insert: try every producible byte, cost 1, no input consumed.
+                    let b = (op - 2) as u8;
+                    parser
+                        .try_push_byte_definitive(Some(b))
+                        .then(|| (RepairOp::Insert(b), pos, cost + 1, 0))
+                }
+            };
+
+            let Some((op, new_pos, new_cost, new_streak)) = child else {
+                continue;
+            };
+
+            *steps += 1;
+            if *steps > max_steps {
+                return Err(());
+            }
+
+            if new_cost > bound {
+                *next_bound = (*next_bound).min(new_cost);
+                continue;
+            }
+
+            repairs.push(op);
+
+            if new_streak >= shift_threshold || (new_pos >= input.len() && parser.is_accepting())
+            {
+                return Ok(true);
+            }
+
+            stack.push(Frame {
+                cp: parser.checkpoint(),
+                pos: new_pos,
+                cost: new_cost,
+                shift_streak: new_streak,
+                entry_repairs_len: repairs.len(),
+                next_op: 0,
+            });
+        }
+
+        Ok(false)
+    }
+
+    /// Search for a minimal-cost sequence of repairs that lets `parser`
+    /// consume (a possibly-edited version of) `input`. Declares success
+    /// once a configuration has `Shift`ed `shift_threshold` real input
+    /// bytes in a row with no repair in between, or once all of `input`
+    /// has been consumed and the parser is in an accepting state.
+    ///
+    /// Bounded by `max_steps` ops that were applied successfully (attempts
+    /// the parser rejects are not counted) across every pass; returns
+    /// `None` if the budget runs out, or once the whole search space (every
+    /// cost bound) has been exhausted, before a success configuration is
+    /// reached.
+    pub fn repair_parse(
+        parser: &Parser,
+        input: &[u8],
+        shift_threshold: usize,
+        max_steps: usize,
+    ) -> Option<(Vec<RepairOp>, Parser)> {
+        let mut p = parser.fork();
+        let mut repairs = Vec::new();
+        let mut steps = 0usize;
+        let mut bound = 0usize;
+
+        // The success condition below (`new_pos >= input.len() &&
+        // parser.is_accepting()`) is only ever tested after a child op has
+        // been computed, so a parser that already satisfies it at the root
+        // (pos == 0) would otherwise never be recognized: with
+        // `input.is_empty()`, `pos < input.len()` is false for both Shift
+        // and Delete, so if every Insert byte is also rejected, every op at
+        // the root frame falls through to `None -> continue`, `next_bound`
+        // never updates, and `dfs_round` reports "no repair exists" even
+        // though the zero-cost answer `(vec![], p)` was there from the
+        // start. Check that up front.
+        if input.is_empty() && p.is_accepting() {
+            return Some((repairs, p));
+        }
+
+        loop {
+            let mut next_bound = usize::MAX;
+            match dfs_round(
+                &mut p,
+                input,
+                shift_threshold,
+                bound,
+                &mut steps,
+                max_steps,
+                &mut next_bound,
+                &mut repairs,
+            ) {
+                Err(()) => return None,
+                Ok(true) => return Some((repairs, p)),
+                Ok(false) => {
+                    if next_bound == usize::MAX {
+                        return None; // whole search space exhausted, no repair exists
+                    }
+                    bound = next_bound;
+                }
+            }
+        }
+    }
+}
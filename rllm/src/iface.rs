@@ -11,40 +11,162 @@ use aicirt::{
     shm::Shm,
 };
 use anyhow::Result;
-use futures::future::select_all;
+use futures::{future::select_all, stream, Stream};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::{
     process::{Child, Command},
     time::Duration,
 };
-use tokio::{signal::unix::SignalKind, sync::oneshot};
+use tokio::{
+    signal::unix::SignalKind,
+    sync::{mpsc, oneshot},
+};
+
+/// Returned by [`AsyncCmdChannel::exec_timeout`] when the deadline elapses
+/// before a response for the request arrives.
+#[derive(Debug, Clone)]
+pub struct TimeoutError {
+    pub op: String,
+    pub deadline: Duration,
+}
+
+impl fmt::Display for TimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "timed out waiting {:?} for response to op {:?}",
+            self.deadline, self.op
+        )
+    }
+}
+
+impl std::error::Error for TimeoutError {}
+
+/// Tracks a single outstanding `$rid` in `pending_reqs` (and, for a streaming
+/// op, its `pending_streams` entry); removes both on drop so a cancelled
+/// (dropped) `exec`/`exec_streaming` future never leaves a stale sender
+/// behind.
+struct PendingGuard {
+    pending_reqs: Arc<Mutex<HashMap<String, oneshot::Sender<Value>>>>,
+    pending_streams: Option<Arc<Mutex<HashMap<String, mpsc::UnboundedSender<Progress>>>>>,
+    rid: String,
+    done: bool,
+}
 
-pub struct CmdChannel {
+impl PendingGuard {
+    fn disarm(mut self) {
+        self.done = true;
+    }
+}
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        if !self.done {
+            self.pending_reqs.lock().unwrap().remove(&self.rid);
+            if let Some(pending_streams) = &self.pending_streams {
+                pending_streams.lock().unwrap().remove(&self.rid);
+            }
+        }
+    }
+}
+
+/// Frame tag that can never start a JSON frame (those always start with
+/// `{`, 0x7B), used to tell binary fast-path frames apart from the regular
+/// JSON ones on the same `MessageChannel`.
+const BIN_FRAME_TAG: u8 = 0xfe;
+/// Set on the response op id to signal the binary op failed; the payload is
+/// then a UTF-8 error message instead of raw response bytes.
+const BIN_ERROR_FLAG: u16 = 0x8000;
+
+fn encode_bin_frame(op: u16, rid: u32, payload: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(1 + 2 + 4 + 4 + payload.len());
+    buf.push(BIN_FRAME_TAG);
+    buf.extend_from_slice(&op.to_le_bytes());
+    buf.extend_from_slice(&rid.to_le_bytes());
+    buf.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    buf.extend_from_slice(payload);
+    buf
+}
+
+fn decode_bin_frame(data: &[u8]) -> Result<(u16, u32, &[u8])> {
+    if data.first() != Some(&BIN_FRAME_TAG) || data.len() < 11 {
+        return Err(anyhow::anyhow!("malformed binary frame header"));
+    }
+    let op = u16::from_le_bytes([data[1], data[2]]);
+    let rid = u32::from_le_bytes([data[3], data[4], data[5], data[6]]);
+    let len = u32::from_le_bytes([data[7], data[8], data[9], data[10]]) as usize;
+    let payload = &data[11..];
+    if payload.len() != len {
+        return Err(anyhow::anyhow!(
+            "binary frame length mismatch: header says {len}, got {}",
+            payload.len()
+        ));
+    }
+    Ok((op, rid, payload))
+}
+
+/// The I/O boundary `CmdChannel`/`AsyncCmdChannel` talk over. Implemented for
+/// the real `MessageChannel` (shared-memory IPC with `aicirt`) and for
+/// `LoopbackTransport` in tests, so the request/response correlation and
+/// error-path decoding logic can be driven without spawning a real runtime.
+pub trait Transport: Send + 'static {
+    fn send(&mut self, data: &[u8]) -> Result<()>;
+    fn recv(&self, timeout: &Duration) -> Result<Vec<u8>>;
+}
+
+impl Transport for MessageChannel {
+    fn send(&mut self, data: &[u8]) -> Result<()> {
+        MessageChannel::send(self, data)
+    }
+
+    fn recv(&self, timeout: &Duration) -> Result<Vec<u8>> {
+        MessageChannel::recv(self, timeout)
+    }
+}
+
+pub struct CmdChannel<T: Transport = MessageChannel> {
     cmd_pending: bool,
-    cmd_ch: MessageChannel,
-    resp_ch: MessageChannel,
+    cmd_ch: T,
+    resp_ch: T,
     busy_wait_duration: Duration,
+    /// Whether `aicirt` advertised `exec_bin` support in its `ping` response.
+    /// Set by [`RtCore::handshake`]; `false` until then, so `exec_bin` fails
+    /// closed rather than assuming support.
+    bin_ops: bool,
 }
 
 const M: usize = 1 << 20;
 
-impl CmdChannel {
+impl CmdChannel<MessageChannel> {
     pub fn new(
         json_size: usize,
         pref: &str,
         suff: &str,
         busy_wait_duration: Duration,
     ) -> Result<Self> {
-        Ok(Self {
+        Ok(Self::from_transport(
+            MessageChannel::new(&format!("{}cmd{}", pref, suff), json_size * M)?,
+            MessageChannel::new(&format!("{}resp{}", pref, suff), json_size * M)?,
+            busy_wait_duration,
+        ))
+    }
+}
+
+impl<T: Transport> CmdChannel<T> {
+    pub fn from_transport(cmd_ch: T, resp_ch: T, busy_wait_duration: Duration) -> Self {
+        Self {
             cmd_pending: false,
-            cmd_ch: MessageChannel::new(&format!("{}cmd{}", pref, suff), json_size * M)?,
-            resp_ch: MessageChannel::new(&format!("{}resp{}", pref, suff), json_size * M)?,
+            cmd_ch,
+            resp_ch,
             busy_wait_duration,
-        })
+            bin_ops: false,
+        }
     }
 
     pub fn send_bytes(&mut self, data: &[u8]) -> Result<()> {
@@ -54,7 +176,7 @@ impl CmdChannel {
         Ok(())
     }
 
-    pub fn exec<T: Serialize, R>(&mut self, op: &str, data: T) -> Result<R>
+    pub fn exec<D: Serialize, R>(&mut self, op: &str, data: D) -> Result<R>
     where
         R: for<'d> Deserialize<'d>,
     {
@@ -62,7 +184,32 @@ impl CmdChannel {
         self.expect(&format!("cmd:{}", op))
     }
 
-    pub fn send<T: Serialize>(&mut self, op: &str, data: T) -> Result<()> {
+    /// Binary fast path for hot, per-token ops: skips serde entirely on both
+    /// the request and response, at the cost of the caller owning framing
+    /// of `payload`. Only usable once `aicirt` has advertised `bin_ops`
+    /// support in its `ping` response (see [`RtCore::handshake`]).
+    pub fn exec_bin(&mut self, op: u16, payload: &[u8]) -> Result<Vec<u8>> {
+        if !self.bin_ops {
+            return Err(anyhow::anyhow!(
+                "bin op {op} not supported: aicirt did not advertise bin_ops during the ping handshake"
+            ));
+        }
+        assert!(!self.cmd_pending);
+        self.cmd_pending = true;
+        self.cmd_ch.send(&encode_bin_frame(op, 0, payload))?;
+        let bytes = self.resp_ch.recv(&self.busy_wait_duration)?;
+        self.cmd_pending = false;
+        let (resp_op, _rid, resp_payload) = decode_bin_frame(&bytes)?;
+        if resp_op & BIN_ERROR_FLAG != 0 {
+            return Err(anyhow::anyhow!(
+                "bin op {op} failed: {}",
+                String::from_utf8_lossy(resp_payload)
+            ));
+        }
+        Ok(resp_payload.to_vec())
+    }
+
+    pub fn send<D: Serialize>(&mut self, op: &str, data: D) -> Result<()> {
         let mut value = serde_json::to_value(data)?;
         value["op"] = json!(op);
         let bytes = serde_json::to_vec(&value)?;
@@ -96,25 +243,33 @@ impl CmdChannel {
     }
 }
 
-pub struct AiciRtIface {
-    cmd: CmdChannel,
-    pub bin_shm: Shm,
-    pub side_cmd: AsyncCmdChannel,
-    #[allow(dead_code)]
-    child: Child,
+/// How `AiciRtIface`'s supervisor reacts when the `aicirt` child exits on its
+/// own (crash, OOM, killed).
+#[derive(Clone, Copy, Debug)]
+pub enum RestartPolicy {
+    /// Leave the runtime dead; pending and future requests just fail.
+    Never,
+    /// Respawn up to `max_attempts` times after each unexpected exit, waiting
+    /// `backoff * attempt_number` between each try. The count starts over
+    /// once a respawn succeeds.
+    MaxAttempts { max_attempts: u32, backoff: Duration },
 }
 
-pub struct Args {
-    pub aicirt: String,
-    pub tokenizer: String,
-    pub json_size: usize,
-    pub bin_size: usize,
-    pub shm_prefix: String,
-    pub busy_wait_time: u64,
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        RestartPolicy::Never
+    }
 }
 
-impl AiciRtIface {
-    pub fn start_aicirt(args: &Args, tok_trie: &TokTrie) -> Result<Self> {
+struct RtCore {
+    cmd: CmdChannel,
+    bin_shm: Shm,
+    side_cmd: AsyncCmdChannel,
+    child: Child,
+}
+
+impl RtCore {
+    fn spawn(args: &Args) -> Result<Self> {
         let busy_wait_time = Duration::from_millis(args.busy_wait_time);
         let shm_name = MessageChannel::shm_name(&args.shm_prefix) + "-bin";
         let cmd = CmdChannel::new(args.json_size, &args.shm_prefix, "", busy_wait_time)?;
@@ -133,96 +288,517 @@ impl AiciRtIface {
             .arg("--server")
             .spawn()?;
 
-        let pid = child.id() as libc::c_int;
-        let default_panic_hook = std::panic::take_hook();
-
-        std::panic::set_hook(Box::new(move |panic_info| {
-            eprintln!("killing {pid}");
-            unsafe {
-                libc::kill(-pid, libc::SIGTERM);
-            }
-            default_panic_hook(panic_info);
-            std::process::exit(100);
-        }));
-
-        let _killer = tokio::spawn(async move {
-            let sigs = vec![
-                SignalKind::interrupt(),
-                SignalKind::quit(),
-                SignalKind::terminate(),
-            ];
-
-            let mut sigs = sigs
-                .iter()
-                .map(|s| tokio::signal::unix::signal(*s).unwrap())
-                .collect::<Vec<_>>();
-
-            loop {
-                let futures: Vec<_> = sigs.iter_mut().map(|s| s.recv()).collect();
-                let pinned_futures: Vec<_> = futures.into_iter().map(|f| Box::pin(f)).collect();
-                select_all(pinned_futures).await;
-                log::info!("Killing child process");
-                unsafe {
-                    libc::kill(-pid, libc::SIGTERM);
-                }
-            }
-        });
-
-        let mut r = Self {
+        Ok(RtCore {
             cmd,
-            side_cmd,
             bin_shm,
+            side_cmd,
             child,
-        };
+        })
+    }
 
-        let _: Value = r.cmd.exec("ping", json!({}))?;
-        let tokens: TokensResp = r.cmd.exec("tokens", json!({}))?;
+    fn handshake(&mut self, vocab_size: usize) -> Result<()> {
+        let ping: Value = self.cmd.exec("ping", json!({}))?;
+        // Capability negotiation: older `aicirt` builds don't send `bin_ops`
+        // at all, which `as_bool()` on a missing key turns into `None`, so we
+        // conservatively treat that the same as "not supported".
+        let bin_ops = ping.get("bin_ops").and_then(Value::as_bool).unwrap_or(false);
+        self.cmd.bin_ops = bin_ops;
+        self.side_cmd.set_bin_ops_supported(bin_ops);
+        let tokens: TokensResp = self.cmd.exec("tokens", json!({}))?;
 
         // well, this is somewhat unlikely as we're passing the same toknizer name down...
-        if tokens.vocab_size != tok_trie.info().vocab_size {
+        if tokens.vocab_size != vocab_size {
             return Err(anyhow::anyhow!(
-                "Vocab size mismatch: {:?} != {:?}",
+                "Vocab size mismatch: {:?} != {}",
                 tokens,
-                tok_trie.info()
+                vocab_size
             ));
         }
 
-        Ok(r)
+        Ok(())
+    }
+}
+
+pub struct AiciRtIface {
+    core: Arc<Mutex<RtCore>>,
+    #[allow(dead_code)]
+    supervisor: thread::JoinHandle<()>,
+}
+
+#[derive(Clone)]
+pub struct Args {
+    pub aicirt: String,
+    pub tokenizer: String,
+    pub json_size: usize,
+    pub bin_size: usize,
+    pub shm_prefix: String,
+    pub busy_wait_time: u64,
+    pub restart_policy: RestartPolicy,
+}
+
+impl AiciRtIface {
+    pub fn start_aicirt(args: &Args, tok_trie: &TokTrie) -> Result<Self> {
+        let core = RtCore::spawn(args)?;
+        // Shared with `supervise_aicirt`, which swaps in the live pid on every
+        // respawn; the panic hook and signal-forwarding task below must always
+        // signal whichever `aicirt` process is currently running, not just the
+        // one that existed when `start_aicirt` was called.
+        let pid = Arc::new(AtomicI32::new(core.child.id() as libc::c_int));
+        let default_panic_hook = std::panic::take_hook();
+
+        {
+            let pid = pid.clone();
+            std::panic::set_hook(Box::new(move |panic_info| {
+                let pid = pid.load(Ordering::SeqCst);
+                eprintln!("killing {pid}");
+                unsafe {
+                    libc::kill(-pid, libc::SIGTERM);
+                }
+                default_panic_hook(panic_info);
+                std::process::exit(100);
+            }));
+        }
+
+        let _killer = {
+            let pid = pid.clone();
+            tokio::spawn(async move {
+                let sigs = vec![
+                    SignalKind::interrupt(),
+                    SignalKind::quit(),
+                    SignalKind::terminate(),
+                ];
+
+                let mut sigs = sigs
+                    .iter()
+                    .map(|s| tokio::signal::unix::signal(*s).unwrap())
+                    .collect::<Vec<_>>();
+
+                loop {
+                    let futures: Vec<_> = sigs.iter_mut().map(|s| s.recv()).collect();
+                    let pinned_futures: Vec<_> =
+                        futures.into_iter().map(|f| Box::pin(f)).collect();
+                    select_all(pinned_futures).await;
+                    log::info!("Killing child process");
+                    unsafe {
+                        libc::kill(-pid.load(Ordering::SeqCst), libc::SIGTERM);
+                    }
+                }
+            })
+        };
+
+        let vocab_size = tok_trie.info().vocab_size;
+        let core = Arc::new(Mutex::new(core));
+        core.lock().unwrap().handshake(vocab_size)?;
+
+        let supervisor = {
+            let core = core.clone();
+            let args = args.clone();
+            let pid = pid.clone();
+            thread::spawn(move || supervise_aicirt(core, args, vocab_size, pid))
+        };
+
+        Ok(Self { core, supervisor })
     }
 
     pub fn aici_pre(&mut self, req: AiciPreProcessReq) -> Result<AiciPreProcessResp> {
-        self.cmd.exec("pre_process", req)
+        self.core.lock().unwrap().cmd.exec("pre_process", req)
+    }
+
+    /// A handle to the side channel that survives respawns: safe to hold on
+    /// to (including across `await` points and in long-lived state) rather
+    /// than re-fetching before every use. See [`SideCmd`].
+    pub fn side_cmd(&self) -> SideCmd {
+        SideCmd {
+            core: self.core.clone(),
+        }
+    }
+
+    pub fn with_bin_shm<R>(&self, f: impl FnOnce(&mut Shm) -> R) -> R {
+        f(&mut self.core.lock().unwrap().bin_shm)
     }
 }
 
+/// A [`side_cmd`](AiciRtIface::side_cmd) handle. Holds the same
+/// `Arc<Mutex<RtCore>>` `AiciRtIface` does, rather than a snapshot of
+/// whichever `AsyncCmdChannel` happened to be live when it was obtained, so
+/// it keeps working across a respawn: every call here briefly locks `core`
+/// to clone out the *current* `AsyncCmdChannel` (an `Arc`-bump, not a real
+/// channel rebuild) and immediately drops the lock before doing any actual
+/// (possibly slow/async) I/O on that clone. A `SideCmd` is safe to cache for
+/// as long as you like, including across awaits.
 #[derive(Clone)]
-pub struct AsyncCmdChannel {
+pub struct SideCmd {
+    core: Arc<Mutex<RtCore>>,
+}
+
+impl SideCmd {
+    fn live(&self) -> AsyncCmdChannel {
+        self.core.lock().unwrap().side_cmd.clone()
+    }
+
+    /// Whether the `AsyncCmdChannel` currently live in `core` is poisoned,
+    /// i.e. `aicirt` has exited and `supervise_aicirt` hasn't finished
+    /// respawning and re-handshaking yet. Unlike the old cached-handle
+    /// hazard, this reflects the *current* instance, not whatever was live
+    /// when this `SideCmd` was obtained.
+    pub fn is_poisoned(&self) -> bool {
+        self.live().poisoned.load(Ordering::SeqCst)
+    }
+
+    pub async fn mk_module(&self, req: MkModuleReq) -> Result<MkModuleResp> {
+        self.live().mk_module(req).await
+    }
+
+    pub async fn instantiate(&self, req: InstantiateReq) -> Result<()> {
+        self.live().instantiate(req).await
+    }
+
+    pub async fn exec<D: Serialize, R>(&self, op: &str, data: D) -> Result<R>
+    where
+        R: for<'d> Deserialize<'d>,
+    {
+        self.live().exec(op, data).await
+    }
+
+    /// Like [`Self::exec`], but fails with a [`TimeoutError`] if no response
+    /// arrives within `deadline`.
+    pub async fn exec_timeout<D: Serialize, R>(
+        &self,
+        op: &str,
+        data: D,
+        deadline: Duration,
+    ) -> Result<R>
+    where
+        R: for<'d> Deserialize<'d>,
+    {
+        self.live().exec_timeout(op, data, deadline).await
+    }
+
+    /// Binary fast path for hot ops; see [`AsyncCmdChannel::exec_bin`].
+    pub async fn exec_bin(&self, op: u16, payload: &[u8]) -> Result<Vec<u8>> {
+        self.live().exec_bin(op, payload).await
+    }
+
+    /// Like [`Self::exec`], but for ops that report progress before
+    /// completing; see [`AsyncCmdChannel::exec_streaming`].
+    pub fn exec_streaming<D: Serialize, R>(
+        &self,
+        op: &str,
+        data: D,
+    ) -> Result<(
+        impl Stream<Item = Progress>,
+        impl std::future::Future<Output = Result<R>>,
+    )>
+    where
+        R: for<'d> Deserialize<'d>,
+    {
+        self.live().exec_streaming(op, data)
+    }
+}
+
+/// Blocks waiting on the `aicirt` child (via a raw `waitpid` on its pid, so
+/// the `RtCore` lock is never held while the child is alive) and, on
+/// unexpected exit, fails all pending `side_cmd` requests and, per
+/// `args.restart_policy`, respawns a fresh child (with new
+/// `CmdChannel`/`Shm`/`AsyncCmdChannel`) and re-runs the handshake before
+/// resuming supervision. `pid` is shared with `start_aicirt`'s panic hook and
+/// signal-forwarding task, and is updated here on every respawn so both keep
+/// signaling the live child instead of a stale, already-dead one.
+///
+/// A failed respawn attempt retries `RtCore::spawn` directly (with the
+/// usual backoff) rather than going back to `waitpid`: there's no child
+/// running yet to wait on in that case, so only a successfully-spawned and
+/// handshaken child takes us back to the top of the loop to wait on it.
+fn supervise_aicirt(core: Arc<Mutex<RtCore>>, args: Args, vocab_size: usize, pid: Arc<AtomicI32>) {
+    let max_attempts = match args.restart_policy {
+        RestartPolicy::Never => 0,
+        RestartPolicy::MaxAttempts { max_attempts, .. } => max_attempts,
+    };
+    let mut attempt = 0;
+
+    'supervise: loop {
+        let mut wstatus: libc::c_int = 0;
+        let status = unsafe {
+            if libc::waitpid(pid.load(Ordering::SeqCst), &mut wstatus, 0) < 0 {
+                "waitpid() failed".to_string()
+            } else if libc::WIFEXITED(wstatus) {
+                format!("code {}", libc::WEXITSTATUS(wstatus))
+            } else if libc::WIFSIGNALED(wstatus) {
+                format!("signal {}", libc::WTERMSIG(wstatus))
+            } else {
+                format!("status {}", wstatus)
+            }
+        };
+        log::error!("aicirt runtime exited unexpectedly: {status}");
+
+        {
+            let core = core.lock().unwrap();
+            poison_pending(
+                &core.side_cmd.pending_reqs,
+                &core.side_cmd.pending_bin_reqs,
+                &core.side_cmd.pending_streams,
+                &core.side_cmd.poisoned,
+                &format!("aicirt runtime exited ({status})"),
+            );
+        }
+
+        // Retry spawn attempts here, without falling through to the
+        // `waitpid` at the top of the outer loop: until a respawn actually
+        // succeeds there's no child running to wait on, and waitpid()-ing
+        // the previous (already-reaped) pid again would fail instantly,
+        // get logged as a second "aicirt runtime exited unexpectedly", and
+        // re-poison an already-empty pending map, making a respawn failure
+        // look like a repeated runtime crash.
+        loop {
+            if attempt >= max_attempts {
+                log::error!("aicirt restart policy exhausted; giving up");
+                return;
+            }
+            attempt += 1;
+
+            if let RestartPolicy::MaxAttempts { backoff, .. } = args.restart_policy {
+                thread::sleep(backoff * attempt);
+            }
+
+            log::info!("respawning aicirt (attempt {attempt}/{max_attempts})");
+            match RtCore::spawn(&args).and_then(|mut new_core| {
+                new_core.handshake(vocab_size)?;
+                Ok(new_core)
+            }) {
+                Ok(new_core) => {
+                    pid.store(new_core.child.id() as libc::c_int, Ordering::SeqCst);
+                    *core.lock().unwrap() = new_core;
+                    attempt = 0;
+                    continue 'supervise;
+                }
+                Err(e) => {
+                    log::error!("failed to respawn aicirt: {e:?}");
+                }
+            }
+        }
+    }
+}
+
+type BinResult = std::result::Result<Vec<u8>, String>;
+
+pub struct AsyncCmdChannel<T: Transport = MessageChannel> {
     pending_reqs: Arc<Mutex<HashMap<String, oneshot::Sender<Value>>>>,
-    cmd_ch: Arc<Mutex<MessageChannel>>,
+    pending_bin_reqs: Arc<Mutex<HashMap<u32, oneshot::Sender<BinResult>>>>,
+    pending_streams: Arc<Mutex<HashMap<String, mpsc::UnboundedSender<Progress>>>>,
+    next_bin_rid: Arc<AtomicU32>,
+    cmd_ch: Arc<Mutex<T>>,
+    poisoned: Arc<AtomicBool>,
+    /// Whether `aicirt` advertised `exec_bin` support during the `ping`
+    /// handshake; set by [`RtCore::handshake`] via [`Self::set_bin_ops_supported`].
+    bin_ops: Arc<AtomicBool>,
 }
 
-impl AsyncCmdChannel {
+/// A single `"type":"progress"` frame forwarded to an [`AsyncCmdChannel::exec_streaming`]
+/// caller before the terminal `"ok"`/`"error"` frame arrives.
+pub type Progress = Value;
+
+// Manual `Clone` so that cloning an `AsyncCmdChannel<T>` doesn't require
+// `T: Clone` (every field is already behind an `Arc`).
+impl<T: Transport> Clone for AsyncCmdChannel<T> {
+    fn clone(&self) -> Self {
+        Self {
+            pending_reqs: self.pending_reqs.clone(),
+            pending_bin_reqs: self.pending_bin_reqs.clone(),
+            pending_streams: self.pending_streams.clone(),
+            next_bin_rid: self.next_bin_rid.clone(),
+            cmd_ch: self.cmd_ch.clone(),
+            poisoned: self.poisoned.clone(),
+            bin_ops: self.bin_ops.clone(),
+        }
+    }
+}
+
+/// Fails every currently-pending request with `msg` and marks `poisoned` so
+/// future `exec` calls fail fast instead of registering doomed requests.
+fn poison_pending(
+    pending_reqs: &Arc<Mutex<HashMap<String, oneshot::Sender<Value>>>>,
+    pending_bin_reqs: &Arc<Mutex<HashMap<u32, oneshot::Sender<BinResult>>>>,
+    pending_streams: &Arc<Mutex<HashMap<String, mpsc::UnboundedSender<Progress>>>>,
+    poisoned: &Arc<AtomicBool>,
+    msg: &str,
+) {
+    poisoned.store(true, Ordering::SeqCst);
+    // dropping the sender closes the stream; no point forwarding a final
+    // progress frame for an error the caller can get from `exec_streaming`'s
+    // result future instead
+    pending_streams.lock().unwrap().clear();
+    for (_, tx) in pending_reqs.lock().unwrap().drain() {
+        let _ = tx.send(json!({ "type": "error", "error": msg }));
+    }
+    for (_, tx) in pending_bin_reqs.lock().unwrap().drain() {
+        let _ = tx.send(Err(msg.to_string()));
+    }
+}
+
+impl AsyncCmdChannel<MessageChannel> {
     pub fn new(json_size: usize, pref: &str, suff: &str) -> Result<Self> {
         let cmd = CmdChannel::new(json_size, pref, suff, Duration::ZERO)?;
+        Self::from_transport(cmd.cmd_ch, cmd.resp_ch)
+    }
+}
+
+impl<T: Transport> AsyncCmdChannel<T> {
+    /// Build a channel directly from a pair of transports, bypassing shared
+    /// memory entirely. Used by tests to plug in a [`LoopbackTransport`].
+    pub fn from_transport(cmd_ch: T, resp_ch: T) -> Result<Self> {
         let pending_reqs = Arc::new(Mutex::new(HashMap::<String, oneshot::Sender<Value>>::new()));
+        let pending_bin_reqs =
+            Arc::new(Mutex::new(HashMap::<u32, oneshot::Sender<BinResult>>::new()));
+        let pending_streams =
+            Arc::new(Mutex::new(HashMap::<String, mpsc::UnboundedSender<Progress>>::new()));
+        let poisoned = Arc::new(AtomicBool::new(false));
         {
-            let resp_ch = cmd.resp_ch;
             let pending_reqs = pending_reqs.clone();
+            let pending_bin_reqs = pending_bin_reqs.clone();
+            let pending_streams = pending_streams.clone();
+            let poisoned = poisoned.clone();
             thread::spawn(move || loop {
-                let resp = resp_ch.recv(&Duration::ZERO).unwrap();
-                let resp: Value = serde_json::from_slice(&resp).unwrap();
-                let rid = resp["$rid"].as_str().unwrap().to_string();
-                let tx = pending_reqs.lock().unwrap().remove(&rid).unwrap();
-                tx.send(resp).unwrap();
+                let resp = match resp_ch.recv(&Duration::ZERO) {
+                    Ok(resp) => resp,
+                    Err(e) => {
+                        log::error!("AsyncCmdChannel: transport recv failed: {e:?}");
+                        poison_pending(
+                            &pending_reqs,
+                            &pending_bin_reqs,
+                            &pending_streams,
+                            &poisoned,
+                            "aicirt transport failure",
+                        );
+                        break;
+                    }
+                };
+
+                if resp.first() == Some(&BIN_FRAME_TAG) {
+                    let (op, rid, payload) = match decode_bin_frame(&resp) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            log::warn!("AsyncCmdChannel: malformed binary frame: {e:?}");
+                            continue;
+                        }
+                    };
+                    let tx = match pending_bin_reqs.lock().unwrap().remove(&rid) {
+                        Some(tx) => tx,
+                        None => {
+                            log::warn!("AsyncCmdChannel: bin response for unknown $rid {rid}");
+                            continue;
+                        }
+                    };
+                    let result = if op & BIN_ERROR_FLAG != 0 {
+                        Err(String::from_utf8_lossy(payload).to_string())
+                    } else {
+                        Ok(payload.to_vec())
+                    };
+                    let _ = tx.send(result);
+                    continue;
+                }
+
+                let resp: Value = match serde_json::from_slice(&resp) {
+                    Ok(resp) => resp,
+                    Err(e) => {
+                        log::error!("AsyncCmdChannel: malformed response frame: {e:?}");
+                        poison_pending(
+                            &pending_reqs,
+                            &pending_bin_reqs,
+                            &pending_streams,
+                            &poisoned,
+                            "aicirt sent malformed response",
+                        );
+                        break;
+                    }
+                };
+                let rid = match resp["$rid"].as_str() {
+                    Some(rid) => rid.to_string(),
+                    None => {
+                        log::warn!("AsyncCmdChannel: response missing $rid: {resp:?}");
+                        continue;
+                    }
+                };
+
+                if resp["type"].as_str() == Some("progress") {
+                    // intermediate frame: forward to the stream and keep the
+                    // $rid registered for the frames (or terminal reply) that
+                    // follow
+                    match pending_streams.lock().unwrap().get(&rid) {
+                        Some(tx) => {
+                            let _ = tx.send(resp);
+                        }
+                        None => {
+                            log::warn!("AsyncCmdChannel: progress for unknown $rid {rid:?}");
+                        }
+                    }
+                    continue;
+                }
+
+                // a terminal ok/error reply closes the stream, if any, by
+                // dropping its sender
+                pending_streams.lock().unwrap().remove(&rid);
+
+                let tx = match pending_reqs.lock().unwrap().remove(&rid) {
+                    Some(tx) => tx,
+                    None => {
+                        log::warn!("AsyncCmdChannel: response for unknown $rid {rid:?}");
+                        continue;
+                    }
+                };
+                // caller may have dropped the future (PendingGuard already
+                // removed the entry); ignore the send failure in that case
+                let _ = tx.send(resp);
             });
         }
 
         Ok(Self {
             pending_reqs,
-            cmd_ch: Arc::new(Mutex::new(cmd.cmd_ch)),
+            pending_bin_reqs,
+            pending_streams,
+            next_bin_rid: Arc::new(AtomicU32::new(0)),
+            cmd_ch: Arc::new(Mutex::new(cmd_ch)),
+            poisoned,
+            bin_ops: Arc::new(AtomicBool::new(false)),
         })
     }
 
+    /// Record whether `aicirt` advertised `exec_bin` support, per
+    /// [`RtCore::handshake`]'s `ping` negotiation. `false` (the default)
+    /// makes [`Self::exec_bin`] fail closed rather than assume support.
+    pub(crate) fn set_bin_ops_supported(&self, supported: bool) {
+        self.bin_ops.store(supported, Ordering::SeqCst);
+    }
+
+    /// Binary fast path for hot ops (see `CmdChannel::exec_bin`); only valid
+    /// once `aicirt` has advertised support for it during the `ping`
+    /// handshake.
+    pub async fn exec_bin(&self, op: u16, payload: &[u8]) -> Result<Vec<u8>> {
+        if self.poisoned.load(Ordering::SeqCst) {
+            return Err(anyhow::anyhow!(
+                "aicirt runtime gone; cannot exec bin op {op}"
+            ));
+        }
+        if !self.bin_ops.load(Ordering::SeqCst) {
+            return Err(anyhow::anyhow!(
+                "bin op {op} not supported: aicirt did not advertise bin_ops during the ping handshake"
+            ));
+        }
+
+        let rid = self.next_bin_rid.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.pending_bin_reqs.lock().unwrap().insert(rid, tx);
+
+        self.cmd_ch
+            .lock()
+            .unwrap()
+            .send(&encode_bin_frame(op, rid, payload))?;
+
+        match rx.await? {
+            Ok(bytes) => Ok(bytes),
+            Err(msg) => Err(anyhow::anyhow!("bin op {op} failed: {msg}")),
+        }
+    }
+
     pub async fn mk_module(&self, req: MkModuleReq) -> Result<MkModuleResp> {
         self.exec("mk_module", req).await
     }
@@ -231,10 +807,34 @@ impl AsyncCmdChannel {
         self.exec("instantiate", req).await
     }
 
-    pub async fn exec<T: Serialize, R>(&self, op: &str, data: T) -> Result<R>
+    pub async fn exec<D: Serialize, R>(&self, op: &str, data: D) -> Result<R>
+    where
+        R: for<'d> Deserialize<'d>,
+    {
+        self.exec_inner(op, data, None).await
+    }
+
+    /// Like [`Self::exec`], but fails with a [`TimeoutError`] if no response
+    /// arrives within `deadline`. On timeout the `$rid` is removed from
+    /// `pending_reqs` so the entry doesn't linger.
+    pub async fn exec_timeout<D: Serialize, R>(&self, op: &str, data: D, deadline: Duration) -> Result<R>
     where
         R: for<'d> Deserialize<'d>,
     {
+        self.exec_inner(op, data, Some(deadline)).await
+    }
+
+    async fn exec_inner<D: Serialize, R>(&self, op: &str, data: D, deadline: Option<Duration>) -> Result<R>
+    where
+        R: for<'d> Deserialize<'d>,
+    {
+        if self.poisoned.load(Ordering::SeqCst) {
+            return Err(anyhow::anyhow!(
+                "aicirt runtime gone; cannot exec op {:?}",
+                op
+            ));
+        }
+
         let rid = uuid::Uuid::new_v4().to_string();
         let mut data = serde_json::to_value(data)?;
         data["op"] = Value::String(op.to_string());
@@ -242,14 +842,43 @@ impl AsyncCmdChannel {
 
         let (tx, rx) = oneshot::channel();
         self.pending_reqs.lock().unwrap().insert(rid.clone(), tx);
+        let guard = PendingGuard {
+            pending_reqs: self.pending_reqs.clone(),
+            pending_streams: None,
+            rid: rid.clone(),
+            done: false,
+        };
 
         self.cmd_ch
             .lock()
             .unwrap()
             .send(&serde_json::to_vec(&data)?)?;
 
-        let mut resp = rx.await?;
+        let resp = match deadline {
+            None => rx.await?,
+            Some(deadline) => match tokio::time::timeout(deadline, rx).await {
+                Ok(resp) => resp?,
+                Err(_) => {
+                    return Err(TimeoutError {
+                        op: op.to_string(),
+                        deadline,
+                    }
+                    .into())
+                }
+            },
+        };
+        guard.disarm();
 
+        Self::decode_terminal(op, resp)
+    }
+
+    /// Decode a terminal (`"ok"`/`"error"`) frame into the caller's expected
+    /// response type. Shared by [`Self::exec_inner`] and
+    /// [`Self::exec_streaming`], which only differ in how they get here.
+    fn decode_terminal<R>(op: &str, mut resp: Value) -> Result<R>
+    where
+        R: for<'d> Deserialize<'d>,
+    {
         match resp["type"].as_str() {
             Some("ok") => {
                 let data = resp
@@ -275,4 +904,246 @@ impl AsyncCmdChannel {
             }
         }
     }
+
+    /// Like [`Self::exec`], but for ops that report progress before
+    /// completing (e.g. `mk_module` compiling a large Wasm controller).
+    /// Returns a stream of `"type":"progress"` frames for the same `$rid`,
+    /// plus a future that resolves once the terminal `"ok"`/`"error"` frame
+    /// arrives — modeled on a child process's stdout stream completing
+    /// alongside its exit status.
+    pub fn exec_streaming<D: Serialize, R>(
+        &self,
+        op: &str,
+        data: D,
+    ) -> Result<(
+        impl Stream<Item = Progress>,
+        impl std::future::Future<Output = Result<R>>,
+    )>
+    where
+        R: for<'d> Deserialize<'d>,
+    {
+        if self.poisoned.load(Ordering::SeqCst) {
+            return Err(anyhow::anyhow!(
+                "aicirt runtime gone; cannot exec op {:?}",
+                op
+            ));
+        }
+
+        let rid = uuid::Uuid::new_v4().to_string();
+        let mut value = serde_json::to_value(data)?;
+        value["op"] = Value::String(op.to_string());
+        value["$rid"] = Value::String(rid.clone());
+
+        let (progress_tx, progress_rx) = mpsc::unbounded_channel();
+        self.pending_streams
+            .lock()
+            .unwrap()
+            .insert(rid.clone(), progress_tx);
+
+        let (tx, rx) = oneshot::channel();
+        self.pending_reqs.lock().unwrap().insert(rid.clone(), tx);
+        let guard = PendingGuard {
+            pending_reqs: self.pending_reqs.clone(),
+            pending_streams: Some(self.pending_streams.clone()),
+            rid: rid.clone(),
+            done: false,
+        };
+
+        self.cmd_ch
+            .lock()
+            .unwrap()
+            .send(&serde_json::to_vec(&value)?)?;
+
+        let op = op.to_string();
+        let result = async move {
+            let resp = rx.await?;
+            guard.disarm();
+            Self::decode_terminal(&op, resp)
+        };
+        let progress = stream::unfold(progress_rx, |mut rx| async move {
+            rx.recv().await.map(|v| (v, rx))
+        });
+
+        Ok((progress, result))
+    }
+}
+
+/// In-process [`Transport`] backed by a pair of in-memory queues, so
+/// `CmdChannel`/`AsyncCmdChannel` can be exercised in tests without a real
+/// `aicirt` process or shared memory.
+///
+/// `send` pushes onto `outbox` (what the "other side" will read) and `recv`
+/// pops from `inbox` (what the "other side" wrote back); a `LoopbackTransport`
+/// handed to the command channel and its mirror image handed to a
+/// [`scripted_responder`] thread form a connected pair.
+///
+/// A zero `timeout` makes `recv` wait until data arrives, the way the
+/// `AsyncCmdChannel` dispatcher's `Duration::ZERO` read on a `MessageChannel`
+/// blocks; any other `timeout` is a deadline after which `recv` fails.
+#[derive(Clone)]
+pub struct LoopbackTransport {
+    inbox: Arc<Mutex<VecDeque<Vec<u8>>>>,
+    outbox: Arc<Mutex<VecDeque<Vec<u8>>>>,
+}
+
+impl LoopbackTransport {
+    /// Build a connected pair: `(a, b)` such that whatever `a.send()`s shows
+    /// up in `b.recv()` and vice versa.
+    pub fn pair() -> (Self, Self) {
+        let left = Arc::new(Mutex::new(VecDeque::new()));
+        let right = Arc::new(Mutex::new(VecDeque::new()));
+        (
+            Self {
+                inbox: left.clone(),
+                outbox: right.clone(),
+            },
+            Self {
+                inbox: right,
+                outbox: left,
+            },
+        )
+    }
+}
+
+impl Transport for LoopbackTransport {
+    fn send(&mut self, data: &[u8]) -> Result<()> {
+        self.outbox.lock().unwrap().push_back(data.to_vec());
+        Ok(())
+    }
+
+    fn recv(&self, timeout: &Duration) -> Result<Vec<u8>> {
+        let deadline = std::time::Instant::now() + *timeout;
+        loop {
+            if let Some(data) = self.inbox.lock().unwrap().pop_front() {
+                return Ok(data);
+            }
+            if !timeout.is_zero() && std::time::Instant::now() >= deadline {
+                return Err(anyhow::anyhow!("LoopbackTransport: recv timed out"));
+            }
+            thread::yield_now();
+        }
+    }
+}
+
+/// Spawn a background thread that plays the role of `aicirt` on the other end
+/// of `transport`: for every JSON request it reads, it calls `respond` and
+/// writes back whatever `Value` that returns. Intended for tests that drive
+/// an [`AsyncCmdChannel`]/[`CmdChannel`] through a [`LoopbackTransport`].
+pub fn scripted_responder<T: Transport>(
+    mut transport: T,
+    mut respond: impl FnMut(Value) -> Value + Send + 'static,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || loop {
+        let req = match transport.recv(&Duration::from_secs(5)) {
+            Ok(req) => req,
+            Err(_) => return,
+        };
+        let req: Value = match serde_json::from_slice(&req) {
+            Ok(req) => req,
+            Err(_) => return,
+        };
+        let resp = respond(req);
+        if transport.send(&serde_json::to_vec(&resp).unwrap()).is_err() {
+            return;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn echo_ok(req: Value) -> Value {
+        json!({
+            "type": "ok",
+            "$rid": req["$rid"],
+            "data": req,
+        })
+    }
+
+    #[tokio::test]
+    async fn exec_round_trips_through_loopback() {
+        let (client, server) = LoopbackTransport::pair();
+        let chan = AsyncCmdChannel::from_transport(client.clone(), client).unwrap();
+        let _responder = scripted_responder(server, echo_ok);
+
+        let resp: Value = chan.exec("ping", json!({"hello": "world"})).await.unwrap();
+        assert_eq!(resp["hello"], "world");
+    }
+
+    #[tokio::test]
+    async fn exec_surfaces_error_responses() {
+        let (client, server) = LoopbackTransport::pair();
+        let chan = AsyncCmdChannel::from_transport(client.clone(), client).unwrap();
+        let _responder = scripted_responder(server, |req| {
+            json!({ "type": "error", "$rid": req["$rid"], "error": "boom" })
+        });
+
+        let err = chan
+            .exec::<_, Value>("ping", json!({}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("boom"));
+    }
+
+    #[tokio::test]
+    async fn exec_rejects_response_missing_data() {
+        let (client, server) = LoopbackTransport::pair();
+        let chan = AsyncCmdChannel::from_transport(client.clone(), client).unwrap();
+        let _responder =
+            scripted_responder(server, |req| json!({ "type": "ok", "$rid": req["$rid"] }));
+
+        let err = chan
+            .exec::<_, Value>("ping", json!({}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("no 'data'"));
+    }
+
+    #[test]
+    fn unknown_rid_response_is_ignored_not_fatal() {
+        let (client, mut server) = LoopbackTransport::pair();
+        // A response for an rid nobody is waiting on should be logged and
+        // skipped by the dispatcher thread, not crash it.
+        server
+            .send(&serde_json::to_vec(&json!({ "type": "ok", "$rid": "no-such-rid", "data": 1 })).unwrap())
+            .unwrap();
+        let _chan = AsyncCmdChannel::from_transport(client.clone(), client).unwrap();
+        // give the dispatcher thread a moment to drain the bogus frame
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn exec_streaming_forwards_progress_then_resolves() {
+        use futures::StreamExt;
+
+        let (client, mut server) = LoopbackTransport::pair();
+        let chan = AsyncCmdChannel::from_transport(client.clone(), client).unwrap();
+
+        let (mut progress, result) = chan
+            .exec_streaming::<_, Value>("mk_module", json!({"wasm": "..."}))
+            .unwrap();
+
+        // drive the "aicirt" side by hand: one request, two replies (a
+        // progress frame followed by the terminal one) on the same $rid
+        thread::spawn(move || {
+            let req: Value =
+                serde_json::from_slice(&server.recv(&Duration::from_secs(1)).unwrap()).unwrap();
+            let rid = req["$rid"].clone();
+            server
+                .send(&serde_json::to_vec(&json!({ "type": "progress", "$rid": rid, "pct": 50 })).unwrap())
+                .unwrap();
+            server
+                .send(&serde_json::to_vec(&json!({ "type": "ok", "$rid": rid, "data": {"done": true} })).unwrap())
+                .unwrap();
+        });
+
+        let first = progress.next().await.unwrap();
+        assert_eq!(first["pct"], 50);
+        // the terminal frame closes the stream
+        assert!(progress.next().await.is_none());
+
+        let resp: Value = result.await.unwrap();
+        assert_eq!(resp["done"], true);
+    }
 }